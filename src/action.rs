@@ -4,6 +4,20 @@ mod select;
 mod flatten;
 mod sort;
 mod filter;
+mod grep;
+mod group;
+mod limit;
+mod describe;
+mod distinct;
+mod count_by;
+mod pivot;
+mod join;
+mod with;
+mod sample;
+mod window;
+mod bucket;
+mod extract;
+mod mask;
 
 pub trait Action {
     fn apply(&self, values: Vec<Value>) -> Vec<Value>;
@@ -11,5 +25,25 @@ pub trait Action {
 
 pub use select::Select;
 pub use flatten::Flatten;
-pub use sort::Sort;
+pub use sort::{Sort, Top};
 pub use filter::Filter;
+pub use grep::Grep;
+pub use group::Group;
+pub use limit::Limit;
+pub use describe::Describe;
+pub use distinct::{Canonical, Distinct};
+pub use count_by::CountBy;
+pub use pivot::{Pivot, Unpivot};
+pub use join::Join;
+pub use with::With;
+pub use sample::Sample;
+pub use window::Window;
+pub use bucket::Bucket;
+pub use extract::{Extract, Split};
+pub use mask::Mask;
+
+// The integer a whole float equals. Beyond 2^63 floats can't be trusted to be
+// exact integers, so they stay floats.
+fn as_integer(n: f64) -> Option<i64> {
+    (n.fract() == 0.0 && n.abs() < 9.2e18).then_some(n as i64)
+}
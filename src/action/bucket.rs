@@ -0,0 +1,224 @@
+use std::collections::HashSet;
+
+use serde_json::{Map, Value};
+
+use crate::{expr::Expr, time, Result};
+
+use super::Action;
+
+// Filling is skipped past this many buckets, like a 1s interval over a year.
+const MAX_BUCKETS: i64 = 100_000;
+
+// Rounds a timestamp field down to the start of its interval, like `ts:5m`.
+// RFC 3339 strings and epoch numbers are read, and buckets are RFC 3339 in UTC.
+// Other values, and timestamps too far out to round, are left as they are.
+pub struct Bucket<'a> {
+    pub name: &'a str,
+    pub field: Expr<'a>,
+    // In millis
+    pub interval: i64,
+}
+
+// Adds the buckets missing between the first and the last one, once rows are
+// counted or grouped by bucket. Numbers are zero in added rows, and rows end up
+// in bucket order.
+pub struct Fill<'a> {
+    pub name: &'a str,
+    pub interval: i64,
+}
+
+impl<'a> Bucket<'a> {
+    // Parses `field:interval`, with an interval like `30s`, `5m`, `1h` or `1d`
+    pub fn parse(spec: &'a Option<String>) -> Result<Option<Self>> {
+        let Some(spec) = spec else {
+            return Ok(None);
+        };
+
+        let (field, interval) = spec
+            .rsplit_once(':')
+            .ok_or("Expected a bucket like `ts:5m`")?;
+
+        let interval = time::parse_duration(interval.trim())
+            .filter(|interval| *interval > 0)
+            .ok_or("Expected a bucket interval like `30s`, `5m`, `1h` or `1d`")?;
+
+        if field.trim().is_empty() {
+            return Err("Empty bucket field");
+        }
+
+        Ok(Some(Self {
+            name: field.trim(),
+            field: Expr::parse(field)?,
+            interval,
+        }))
+    }
+
+    pub fn fill(&self) -> Fill<'a> {
+        Fill {
+            name: self.name,
+            interval: self.interval,
+        }
+    }
+}
+
+impl<'a> Action for Bucket<'a> {
+    fn apply(&self, mut values: Vec<Value>) -> Vec<Value> {
+        for row in values.iter_mut() {
+            let Some(millis) = self.field.eval(row).as_ref().and_then(timestamp) else {
+                continue;
+            };
+
+            let Some(bucket) = millis.div_euclid(self.interval).checked_mul(self.interval) else {
+                continue;
+            };
+
+            if let Value::Object(map) = row {
+                map.insert(String::from(self.name), Value::from(time::format(bucket)));
+            }
+        }
+
+        values
+    }
+}
+
+impl<'a> Action for Fill<'a> {
+    fn apply(&self, values: Vec<Value>) -> Vec<Value> {
+        let buckets: Vec<Option<i64>> = values
+            .iter()
+            .map(|row| row.get(self.name).and_then(timestamp))
+            .collect();
+
+        let seen: HashSet<i64> = buckets.iter().flatten().copied().collect();
+
+        let (Some(&first), Some(&last)) = (seen.iter().min(), seen.iter().max()) else {
+            return values;
+        };
+
+        let steps = match last.checked_sub(first) {
+            Some(span) if span / self.interval <= MAX_BUCKETS => span / self.interval,
+            _ => return values,
+        };
+
+        // Numbers are zero, strings empty and anything else null
+        let template: Map<String, Value> = match values.first() {
+            Some(Value::Object(map)) => map
+                .iter()
+                .map(|(key, value)| match value {
+                    Value::Number(n) if n.is_f64() => (key.clone(), Value::from(0.0)),
+                    Value::Number(_) => (key.clone(), Value::from(0)),
+                    Value::String(_) => (key.clone(), Value::from("")),
+                    _ => (key.clone(), Value::Null),
+                })
+                .collect(),
+            _ => Map::new(),
+        };
+
+        let mut rows: Vec<(Option<i64>, Value)> = buckets.into_iter().zip(values).collect();
+
+        let missing = (0..=steps)
+            .map(|i| first + i * self.interval)
+            .filter(|bucket| !seen.contains(bucket));
+
+        for bucket in missing {
+            let mut row = template.clone();
+            row.insert(String::from(self.name), Value::from(time::format(bucket)));
+            rows.push((Some(bucket), Value::Object(row)));
+        }
+
+        // Rows without a bucket go last
+        rows.sort_by_key(|(bucket, _)| (bucket.is_none(), *bucket));
+        rows.into_iter().map(|(_, row)| row).collect()
+    }
+}
+
+fn timestamp(value: &Value) -> Option<i64> {
+    match value {
+        Value::String(s) => time::parse(s),
+        Value::Number(n) => n.as_f64().map(time::from_epoch),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn bucket(spec: &Option<String>) -> Bucket<'_> {
+        Bucket::parse(spec).unwrap().unwrap()
+    }
+
+    #[test]
+    fn rounds_down_to_the_interval() {
+        let spec = Some(String::from("ts:5m"));
+        let rows = vec![
+            json!({"ts": "2026-10-01T10:04:59Z"}),
+            json!({"ts": "2026-10-01T12:07:00+02:00"}),
+            json!({"ts": 1_790_848_800}),
+            json!({"ts": "yesterday"}),
+            json!({}),
+        ];
+
+        assert_eq!(
+            bucket(&spec).apply(rows),
+            vec![
+                json!({"ts": "2026-10-01T10:00:00Z"}),
+                json!({"ts": "2026-10-01T10:05:00Z"}),
+                json!({"ts": "2026-10-01T10:00:00Z"}),
+                json!({"ts": "yesterday"}),
+                json!({}),
+            ]
+        );
+    }
+
+    #[test]
+    fn fills_missing_buckets() {
+        let spec = Some(String::from("ts:5m"));
+        let rows = vec![
+            json!({"ts": "2026-10-01T10:15:00Z", "n": 2, "avg": 1.5, "s": "x"}),
+            json!({"ts": null, "n": 1, "avg": 1.0, "s": "y"}),
+            json!({"ts": "2026-10-01T10:00:00Z", "n": 1, "avg": 2.0, "s": "z"}),
+        ];
+
+        assert_eq!(
+            bucket(&spec).fill().apply(rows),
+            vec![
+                json!({"ts": "2026-10-01T10:00:00Z", "n": 1, "avg": 2.0, "s": "z"}),
+                json!({"ts": "2026-10-01T10:05:00Z", "n": 0, "avg": 0.0, "s": ""}),
+                json!({"ts": "2026-10-01T10:10:00Z", "n": 0, "avg": 0.0, "s": ""}),
+                json!({"ts": "2026-10-01T10:15:00Z", "n": 2, "avg": 1.5, "s": "x"}),
+                json!({"ts": null, "n": 1, "avg": 1.0, "s": "y"}),
+            ]
+        );
+    }
+
+    #[test]
+    fn too_many_buckets_are_not_filled() {
+        let spec = Some(String::from("ts:1s"));
+        let rows = vec![
+            json!({"ts": "2026-01-01T00:00:00Z"}),
+            json!({"ts": "2026-12-01T00:00:00Z"}),
+        ];
+
+        assert_eq!(bucket(&spec).fill().apply(rows.clone()), rows);
+    }
+
+    #[test]
+    fn extreme_epochs_are_left_as_they_are() {
+        let spec = Some(String::from("ts:7m"));
+        let rows = vec![json!({"ts": -1e22})];
+        assert_eq!(bucket(&spec).apply(rows.clone()), rows);
+
+        // The range between them doesn't fit in an i64
+        let rows = vec![json!({"ts": -1e22}), json!({"ts": 1e22})];
+        assert_eq!(bucket(&spec).fill().apply(rows.clone()), rows);
+    }
+
+    #[test]
+    fn invalid_buckets() {
+        for spec in ["ts", "ts:0s", "ts:5y", ":5m", "ts:"] {
+            assert!(Bucket::parse(&Some(String::from(spec))).is_err(), "{}", spec);
+        }
+    }
+}
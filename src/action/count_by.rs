@@ -0,0 +1,173 @@
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+};
+
+use serde_json::{Map, Value};
+
+use crate::{expr::Expr, Result};
+
+use super::{distinct::Canonical, pivot::unique_name, sort::first, Action};
+
+const BAR_WIDTH: usize = 30;
+
+// Replaces rows with one row per distinct value of `field`, with how many rows
+// have it, most frequent first. Ties keep the order values are first seen in.
+// A field named like one of the added columns, like `count`, gets a suffix: `count_2`.
+pub struct CountBy<'a> {
+    pub name: &'a str,
+    pub field: Expr<'a>,
+    // Keeps only the most frequent values
+    pub top: Option<usize>,
+    // Adds a bar scaled to the most frequent value
+    pub bar: bool,
+}
+
+impl<'a> CountBy<'a> {
+    pub fn parse(field: &'a Option<String>, top: Option<usize>, bar: bool) -> Result<Option<Self>> {
+        let Some(field) = field else {
+            return Ok(None);
+        };
+
+        Ok(Some(Self {
+            name: field.trim(),
+            field: Expr::parse(field)?,
+            top,
+            bar,
+        }))
+    }
+}
+
+impl<'a> Action for CountBy<'a> {
+    fn apply(&self, values: Vec<Value>) -> Vec<Value> {
+        let keys: Vec<Value> = values
+            .iter()
+            .map(|row| self.field.eval(row).unwrap_or(Value::Null))
+            .collect();
+
+        // Count and first index of each value
+        let mut counts: HashMap<Canonical, (usize, usize)> = HashMap::new();
+
+        for (i, key) in keys.iter().enumerate() {
+            counts.entry(Canonical(key)).or_insert((0, i)).0 += 1;
+        }
+
+        let distinct = counts.len();
+        let ranked = counts
+            .into_values()
+            .map(|(count, index)| (Reverse(count), index));
+
+        // A top covering every value is a plain sort
+        let ranked = match self.top {
+            Some(n) if n < distinct => first(ranked, n),
+            _ => {
+                let mut ranked: Vec<_> = ranked.collect();
+                ranked.sort_unstable();
+                ranked
+            }
+        };
+
+        let total = values.len();
+        let max = ranked.first().map_or(0, |(Reverse(count), _)| *count);
+
+        let mut taken: HashSet<String> = ["count", "percent", "bar"]
+            .into_iter()
+            .filter(|column| self.bar || *column != "bar")
+            .map(String::from)
+            .collect();
+        let name = unique_name(String::from(self.name), &mut taken);
+
+        ranked
+            .into_iter()
+            .map(|(Reverse(count), index)| {
+                let percent = (count * 1000) as f64 / total as f64;
+
+                let mut row = Map::new();
+                row.insert(name.clone(), keys[index].clone());
+                row.insert(String::from("count"), Value::from(count));
+                row.insert(String::from("percent"), Value::from(percent.round() / 10.0));
+
+                if self.bar {
+                    let width = (count * BAR_WIDTH + max / 2) / max;
+                    row.insert(String::from("bar"), Value::from("█".repeat(width.max(1))));
+                }
+
+                Value::Object(row)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn count_by(field: &str, top: Option<usize>, rows: Vec<Value>) -> Vec<Value> {
+        let field = Some(String::from(field));
+        CountBy::parse(&field, top, false).unwrap().unwrap().apply(rows)
+    }
+
+    fn rows() -> Vec<Value> {
+        [1, 2, 2, 3, 3, 3, 4]
+            .into_iter()
+            .map(|n| json!({"n": n}))
+            .collect()
+    }
+
+    #[test]
+    fn most_frequent_first_with_ties_in_order() {
+        let counts: Vec<Value> = count_by("n", None, rows())
+            .into_iter()
+            .map(|row| row["n"].clone())
+            .collect();
+
+        assert_eq!(counts, [json!(3), json!(2), json!(1), json!(4)]);
+    }
+
+    #[test]
+    fn top() {
+        assert_eq!(
+            count_by("n", Some(1), rows()),
+            vec![json!({"n": 3, "count": 3, "percent": 42.9})]
+        );
+        assert_eq!(count_by("n", Some(0), rows()), Vec::<Value>::new());
+        assert_eq!(count_by("n", Some(usize::MAX), rows()), count_by("n", None, rows()));
+    }
+
+    #[test]
+    fn field_named_like_a_count_column() {
+        let rows = vec![json!({"count": 5}), json!({"count": 5})];
+
+        assert_eq!(
+            count_by("count", None, rows),
+            vec![json!({"count_2": 5, "count": 2, "percent": 100.0})]
+        );
+
+        let field = Some(String::from("bar"));
+        let rows = vec![json!({"bar": "x"})];
+
+        assert_eq!(
+            CountBy::parse(&field, None, true).unwrap().unwrap().apply(rows.clone()),
+            vec![json!({"bar_2": "x", "count": 1, "percent": 100.0, "bar": "█".repeat(30)})]
+        );
+        assert_eq!(
+            count_by("bar", None, rows),
+            vec![json!({"bar": "x", "count": 1, "percent": 100.0})]
+        );
+    }
+
+    #[test]
+    fn equal_values_count_together() {
+        let rows = vec![json!({"n": 1}), json!({"n": 1.0}), json!({})];
+
+        assert_eq!(
+            count_by("n", None, rows),
+            vec![
+                json!({"n": 1, "count": 2, "percent": 66.7}),
+                json!({"n": null, "count": 1, "percent": 33.3}),
+            ]
+        );
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+
+use serde_json::{json, Map, Value};
+
+use super::{
+    distinct::Canonical,
+    group::number,
+    sort::{compare, Collation},
+    Action,
+};
+
+// Replaces rows with one row of summary statistics per column. Columns are the
+// top level keys of the rows, in the order they are first seen.
+pub struct Describe;
+
+const TOP_VALUES: usize = 3;
+const PREVIEW_CHARS: usize = 24;
+
+// Everything seen for one column
+#[derive(Default)]
+struct Column<'v> {
+    types: Vec<(&'static str, usize)>,
+    count: usize,
+    // Frequency of each value, in first-seen order
+    frequencies: HashMap<Canonical<'v>, usize>,
+    first_seen: Vec<&'v Value>,
+    min: Option<&'v Value>,
+    max: Option<&'v Value>,
+    // Welford's online mean and variance
+    numbers: usize,
+    mean: f64,
+    m2: f64,
+    shortest: Option<&'v str>,
+    longest: Option<&'v str>,
+}
+
+impl Action for Describe {
+    fn apply(&self, values: Vec<Value>) -> Vec<Value> {
+        let mut names: Vec<&str> = Vec::new();
+        let mut columns: HashMap<&str, Column> = HashMap::new();
+
+        for row in values.iter() {
+            if let Value::Object(map) = row {
+                for (key, value) in map.iter() {
+                    let column = columns.entry(key).or_insert_with(|| {
+                        names.push(key);
+                        Column::default()
+                    });
+
+                    column.add(value);
+                }
+            }
+        }
+
+        names
+            .into_iter()
+            .map(|name| columns[name].describe(name, values.len()))
+            .collect()
+    }
+}
+
+impl<'v> Column<'v> {
+    fn add(&mut self, value: &'v Value) {
+        let name = type_name(value);
+
+        match self.types.iter_mut().find(|(type_name, _)| *type_name == name) {
+            Some((_, count)) => *count += 1,
+            None => self.types.push((name, 1)),
+        }
+
+        if value.is_null() {
+            return;
+        }
+
+        self.count += 1;
+
+        match self.frequencies.get_mut(&Canonical(value)) {
+            Some(count) => *count += 1,
+            None => {
+                self.frequencies.insert(Canonical(value), 1);
+                self.first_seen.push(value);
+            }
+        }
+
+        match value {
+            Value::Number(n) => {
+                if self.min.is_none_or(|min| compare(value, min, Collation::Binary).is_lt()) {
+                    self.min = Some(value);
+                }
+
+                if self.max.is_none_or(|max| compare(value, max, Collation::Binary).is_gt()) {
+                    self.max = Some(value);
+                }
+
+                let n = n.as_f64().unwrap_or(0.0);
+                self.numbers += 1;
+                let delta = n - self.mean;
+                self.mean += delta / self.numbers as f64;
+                self.m2 += delta * (n - self.mean);
+            }
+            Value::String(s) => {
+                let len = s.chars().count();
+
+                if self.shortest.is_none_or(|shortest| len < shortest.chars().count()) {
+                    self.shortest = Some(s);
+                }
+
+                if self.longest.is_none_or(|longest| len > longest.chars().count()) {
+                    self.longest = Some(s);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn describe(&self, name: &str, rows: usize) -> Value {
+        // Missing values show up as nulls
+        let mut types = self.types.clone();
+        let present: usize = types.iter().map(|(_, count)| count).sum();
+
+        if present < rows {
+            match types.iter_mut().find(|(type_name, _)| *type_name == "null") {
+                Some((_, count)) => *count += rows - present,
+                None => types.push(("null", rows - present)),
+            }
+        }
+
+        let types = match types.as_slice() {
+            [(type_name, _)] => type_name.to_string(),
+            _ => list(types.iter().map(|(type_name, count)| (*type_name, *count))),
+        };
+
+        // Most frequent first, ties in first-seen order
+        let mut top: Vec<(&Value, usize)> = self
+            .first_seen
+            .iter()
+            .map(|value| (*value, self.frequencies[&Canonical(value)]))
+            .collect();
+        top.sort_by(|(_, count1), (_, count2)| count2.cmp(count1));
+        top.truncate(TOP_VALUES);
+        let top: Vec<(String, usize)> = top
+            .into_iter()
+            .map(|(value, count)| (preview(value), count))
+            .collect();
+
+        let (mean, stddev) = match self.numbers {
+            0 => (Value::Null, Value::Null),
+            1 => (number(self.mean), Value::Null),
+            n => (number(self.mean), number((self.m2 / (n - 1) as f64).sqrt())),
+        };
+
+        let mut row = Map::new();
+        row.insert(String::from("column"), json!(name));
+        row.insert(String::from("types"), json!(types));
+        row.insert(String::from("count"), json!(self.count));
+        row.insert(String::from("nulls"), json!(rows - self.count));
+        row.insert(String::from("distinct"), json!(self.frequencies.len()));
+        row.insert(String::from("min"), self.min.cloned().unwrap_or(Value::Null));
+        row.insert(String::from("max"), self.max.cloned().unwrap_or(Value::Null));
+        row.insert(String::from("mean"), mean);
+        row.insert(String::from("stddev"), stddev);
+        row.insert(String::from("shortest"), json!(self.shortest));
+        row.insert(String::from("longest"), json!(self.longest));
+        row.insert(String::from("top"), json!(list(top.iter().map(|(value, count)| (value.as_str(), *count)))));
+
+        Value::Object(row)
+    }
+}
+
+// Formats like `number (3), string (1)`
+fn list<'s>(items: impl Iterator<Item = (&'s str, usize)>) -> String {
+    items
+        .map(|(item, count)| format!("{} ({})", item, count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Strings are shown without quotes, and long values are cut short
+fn preview(value: &Value) -> String {
+    let text = match value {
+        Value::String(s) => s.clone(),
+        _ => value.to_string(),
+    };
+
+    match text.char_indices().nth(PREVIEW_CHARS) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_and_missing_values() {
+        let rows = vec![json!({"n": 1}), json!({"n": 2.0}), json!({"n": 6}), json!({})];
+
+        assert_eq!(
+            Describe.apply(rows),
+            vec![json!({
+                "column": "n",
+                "types": "number (3), null (1)",
+                "count": 3,
+                "nulls": 1,
+                "distinct": 3,
+                "min": 1,
+                "max": 6,
+                "mean": 3,
+                "stddev": 2.6457513110645907,
+                "shortest": null,
+                "longest": null,
+                "top": "1 (1), 2.0 (1), 6 (1)",
+            })]
+        );
+    }
+
+    #[test]
+    fn strings() {
+        let rows = vec![json!({"s": "bb"}), json!({"s": "a"}), json!({"s": "bb"})];
+        let described = Describe.apply(rows).remove(0);
+
+        assert_eq!(described["types"], "string");
+        assert_eq!(described["distinct"], 2);
+        assert_eq!(described["min"], Value::Null);
+        assert_eq!(described["shortest"], "a");
+        assert_eq!(described["longest"], "bb");
+        assert_eq!(described["mean"], Value::Null);
+        assert_eq!(described["top"], "bb (2), a (1)");
+    }
+
+    #[test]
+    fn long_values_are_cut_short() {
+        assert_eq!(preview(&json!("x".repeat(30))), format!("{}...", "x".repeat(24)));
+        assert_eq!(preview(&json!([1, 2])), "[1,2]");
+    }
+}
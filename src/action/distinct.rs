@@ -0,0 +1,306 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    hash::{Hash, Hasher},
+};
+
+use serde_json::{Number, Value};
+
+use crate::{
+    args::Keep,
+    expr::{self, Expr},
+    Result,
+};
+
+use super::Action;
+
+// Drops duplicate rows, or rows with a duplicate key when `fields` are given.
+// Kept rows stay in their original order.
+pub struct Distinct<'a> {
+    pub fields: Vec<Expr<'a>>,
+    pub keep: Keep,
+    // Adds a `count` column with the number of duplicates of each kept row,
+    // named differently when rows already have a `count` field
+    pub count: bool,
+}
+
+impl<'a> Distinct<'a> {
+    // Parses comma separated key fields like `user_id,session`
+    pub fn parse(
+        distinct: bool,
+        distinct_on: &'a Option<String>,
+        keep: Keep,
+        count: bool,
+    ) -> Result<Option<Self>> {
+        let fields = match distinct_on {
+            Some(fields) => expr::split_top_level(fields)
+                .into_iter()
+                .map(|field| Expr::parse(field.trim()))
+                .collect::<Result<_>>()?,
+            None if distinct => Vec::new(),
+            None => return Ok(None),
+        };
+
+        Ok(Some(Self {
+            fields,
+            keep,
+            count,
+        }))
+    }
+}
+
+impl<'a> Action for Distinct<'a> {
+    fn apply(&self, values: Vec<Value>) -> Vec<Value> {
+        let keys: Vec<Value> = match self.fields.is_empty() {
+            true => Vec::new(),
+            false => values
+                .iter()
+                .map(|row| {
+                    let keys = self.fields.iter();
+                    Value::Array(keys.map(|key| key.eval(row).unwrap_or(Value::Null)).collect())
+                })
+                .collect(),
+        };
+
+        let keys = match self.fields.is_empty() {
+            true => &values,
+            false => &keys,
+        };
+
+        // Index of the kept row and number of duplicates, per key
+        let mut seen: HashMap<Canonical, usize> = HashMap::new();
+        let mut kept: Vec<(usize, usize)> = Vec::new();
+
+        for (i, key) in keys.iter().enumerate() {
+            match seen.entry(Canonical(key)) {
+                Entry::Occupied(entry) => {
+                    let (index, count) = &mut kept[*entry.get()];
+                    *count += 1;
+
+                    if let Keep::Last = self.keep {
+                        *index = i;
+                    }
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(kept.len());
+                    kept.push((i, 1));
+                }
+            }
+        }
+
+        kept.sort_unstable();
+
+        let mut values: Vec<Option<Value>> = values.into_iter().map(Some).collect();
+
+        let kept: Vec<(Value, usize)> = kept
+            .into_iter()
+            .filter_map(|(index, count)| Some((values[index].take()?, count)))
+            .collect();
+
+        if !self.count {
+            return kept.into_iter().map(|(row, _)| row).collect();
+        }
+
+        let column = count_column(&kept);
+
+        kept.into_iter()
+            .map(|(mut row, count)| {
+                if let Value::Object(map) = &mut row {
+                    map.insert(column.clone(), Value::from(count));
+                }
+
+                row
+            })
+            .collect()
+    }
+}
+
+// `count`, or when rows already have a field named so, the first of
+// `distinct_count`, `distinct_count_2`, ... they don't have.
+fn count_column(rows: &[(Value, usize)]) -> String {
+    let taken = |name: &str| rows.iter().any(|(row, _)| row.get(name).is_some());
+
+    (1..)
+        .map(|i| match i {
+            1 => String::from("count"),
+            2 => String::from("distinct_count"),
+            i => format!("distinct_count_{}", i - 1),
+        })
+        .find(|name| !taken(name))
+        .unwrap()
+}
+
+// Hashes and compares values by content, so that `{"a":1,"b":2}` equals
+// `{"b":2,"a":1}` and `1` equals `1.0`.
+pub struct Canonical<'v>(pub &'v Value);
+
+impl<'v> Hash for Canonical<'v> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self.0 {
+            Value::Null => state.write_u8(0),
+            Value::Bool(bool) => {
+                state.write_u8(1);
+                bool.hash(state);
+            }
+            Value::Number(n) => {
+                state.write_u8(2);
+                Normalized::new(n).hash(state);
+            }
+            Value::String(s) => {
+                state.write_u8(3);
+                s.hash(state);
+            }
+            Value::Array(arr) => {
+                state.write_u8(4);
+                state.write_usize(arr.len());
+                arr.iter().for_each(|value| Canonical(value).hash(state));
+            }
+            Value::Object(obj) => {
+                state.write_u8(5);
+                state.write_usize(obj.len());
+
+                let mut entries: Vec<_> = obj.iter().collect();
+                entries.sort_unstable_by_key(|(key, _)| *key);
+
+                for (key, value) in entries {
+                    key.hash(state);
+                    Canonical(value).hash(state);
+                }
+            }
+        }
+    }
+}
+
+impl<'v> PartialEq for Canonical<'v> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.0, other.0) {
+            (Value::Null, Value::Null) => true,
+            (Value::Bool(bool1), Value::Bool(bool2)) => bool1 == bool2,
+            (Value::Number(n1), Value::Number(n2)) => Normalized::new(n1) == Normalized::new(n2),
+            (Value::String(s1), Value::String(s2)) => s1 == s2,
+            (Value::Array(arr1), Value::Array(arr2)) => {
+                arr1.len() == arr2.len()
+                    && arr1
+                        .iter()
+                        .zip(arr2.iter())
+                        .all(|(val1, val2)| Canonical(val1) == Canonical(val2))
+            }
+            (Value::Object(obj1), Value::Object(obj2)) => {
+                obj1.len() == obj2.len()
+                    && obj1.iter().all(|(key, val1)| {
+                        obj2.get(key)
+                            .is_some_and(|val2| Canonical(val1) == Canonical(val2))
+                    })
+            }
+            _ => false,
+        }
+    }
+}
+
+impl<'v> Eq for Canonical<'v> {}
+
+// Numbers equal when they are the same number, whatever their representation,
+// so that `1` equals `1.0` and a u64 equals the float it is exactly.
+#[derive(PartialEq, Eq, Hash)]
+enum Normalized {
+    Integer(i128),
+    Float(u64),
+}
+
+impl Normalized {
+    fn new(n: &Number) -> Self {
+        if let Some(i) = n.as_i64() {
+            return Self::Integer(i as i128);
+        }
+
+        if let Some(u) = n.as_u64() {
+            return Self::Integer(u as i128);
+        }
+
+        // Whole floats are exact integers, which fit in an i128 below 2^127
+        let f = n.as_f64().unwrap_or(0.0);
+
+        match f.fract() == 0.0 && f.abs() < 1.7e38 {
+            true => Self::Integer(f as i128),
+            false => Self::Float(f.to_bits()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::DefaultHasher;
+
+    use serde_json::json;
+
+    use super::*;
+
+    fn hash(value: &Value) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        Canonical(value).hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn assert_equal(v1: Value, v2: Value) {
+        assert!(Canonical(&v1) == Canonical(&v2), "{} != {}", v1, v2);
+        assert_eq!(hash(&v1), hash(&v2), "{} and {} hash apart", v1, v2);
+    }
+
+    fn assert_not_equal(v1: Value, v2: Value) {
+        assert!(Canonical(&v1) != Canonical(&v2), "{} == {}", v1, v2);
+    }
+
+    #[test]
+    fn numbers() {
+        assert_equal(json!(1), json!(1.0));
+        assert_equal(json!(-3), json!(-3.0));
+        assert_equal(json!(0), json!(-0.0));
+        assert_equal(json!(10_000_000_000_000_000_000u64), json!(1e19));
+        assert_equal(json!(i64::MAX - 1023), json!(9.223372036854775e18));
+        assert_not_equal(json!(1), json!(1.5));
+        assert_not_equal(json!(9_007_199_254_740_993i64), json!(9_007_199_254_740_992.0));
+        assert_not_equal(json!(1), json!("1"));
+    }
+
+    #[test]
+    fn objects_ignore_key_order() {
+        assert_equal(json!({"a": 1, "b": [1, 2]}), json!({"b": [1.0, 2], "a": 1}));
+        assert_not_equal(json!({"a": 1}), json!({"a": 1, "b": null}));
+        assert_not_equal(json!([1, 2]), json!([2, 1]));
+    }
+
+    fn distinct(fields: Option<&str>, count: bool, rows: Vec<Value>) -> Vec<Value> {
+        let fields = fields.map(String::from);
+
+        Distinct::parse(true, &fields, Keep::First, count)
+            .unwrap()
+            .unwrap()
+            .apply(rows)
+    }
+
+    #[test]
+    fn drops_duplicates() {
+        let rows = vec![json!({"a": 1}), json!({"a": 1.0}), json!({"a": 2})];
+
+        assert_eq!(
+            distinct(None, true, rows),
+            vec![json!({"a": 1, "count": 2}), json!({"a": 2, "count": 1})]
+        );
+    }
+
+    #[test]
+    fn count_column_doesnt_overwrite_fields() {
+        let rows = vec![
+            json!({"a": 1, "count": 7}),
+            json!({"a": 1, "count": 8}),
+            json!({"a": 2, "distinct_count": 0}),
+        ];
+
+        assert_eq!(
+            distinct(Some("a"), true, rows),
+            vec![
+                json!({"a": 1, "count": 7, "distinct_count_2": 2}),
+                json!({"a": 2, "distinct_count": 0, "distinct_count_2": 1}),
+            ]
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use regex::Regex;
+use serde_json::Value;
+
+use crate::{expr::Expr, input, Result};
+
+use super::Action;
+
+// Adds a column for each named group of a regex matched against a string field,
+// like `message=(?P<method>\w+) (?P<path>\S+)`. Captures are typed like CSV
+// fields, and columns are null when the field doesn't match.
+pub struct Extract<'a> {
+    pub fields: Vec<(Expr<'a>, Regex)>,
+}
+
+// Turns a string field into an array of its parts, like `tags=,`. Parts are
+// trimmed and empty ones dropped. Other values are left as they are.
+pub struct Split<'a> {
+    pub fields: Vec<(&'a str, Expr<'a>, &'a str)>,
+}
+
+impl<'a> Extract<'a> {
+    pub fn parse(fields: &'a [String]) -> Result<Self> {
+        let fields = fields
+            .iter()
+            .map(|field| {
+                let (field, pattern) = field
+                    .split_once('=')
+                    .ok_or("Expected an extract like `message=(?P<method>\\w+) (?P<path>\\S+)`")?;
+
+                let pattern = Regex::new(pattern).map_err(|_| "Invalid extract pattern")?;
+
+                if pattern.capture_names().flatten().next().is_none() {
+                    return Err("Extract pattern needs named groups, like `(?P<status>\\d+)`");
+                }
+
+                Ok((Expr::parse(field)?, pattern))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { fields })
+    }
+}
+
+impl<'a> Split<'a> {
+    pub fn parse(fields: &'a [String]) -> Result<Self> {
+        let fields = fields
+            .iter()
+            .map(|field| {
+                let (field, delimiter) = field
+                    .split_once('=')
+                    .ok_or("Expected a split like `tags=,`")?;
+
+                if delimiter.is_empty() {
+                    return Err("Empty split delimiter");
+                }
+
+                Ok((field.trim(), Expr::parse(field)?, delimiter))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { fields })
+    }
+}
+
+impl<'a> Action for Extract<'a> {
+    fn apply(&self, mut values: Vec<Value>) -> Vec<Value> {
+        for row in values.iter_mut() {
+            for (field, pattern) in self.fields.iter() {
+                let value = field.eval(row);
+                let captures = match &value {
+                    Some(Value::String(s)) => pattern.captures(s),
+                    _ => None,
+                };
+
+                let Value::Object(map) = row else {
+                    continue;
+                };
+
+                for (i, name) in pattern.capture_names().enumerate() {
+                    let Some(name) = name else {
+                        continue;
+                    };
+
+                    let value = captures
+                        .as_ref()
+                        .and_then(|captures| captures.get(i))
+                        .map_or(Value::Null, |m| input::infer(m.as_str().to_string()));
+
+                    map.insert(String::from(name), value);
+                }
+            }
+        }
+
+        values
+    }
+}
+
+impl<'a> Action for Split<'a> {
+    fn apply(&self, mut values: Vec<Value>) -> Vec<Value> {
+        for row in values.iter_mut() {
+            for (name, field, delimiter) in self.fields.iter() {
+                let Some(Value::String(s)) = field.eval(row) else {
+                    continue;
+                };
+
+                let parts = s
+                    .split(delimiter)
+                    .map(str::trim)
+                    .filter(|part| !part.is_empty())
+                    .map(Value::from)
+                    .collect();
+
+                if let Value::Object(map) = row {
+                    map.insert(String::from(*name), Value::Array(parts));
+                }
+            }
+        }
+
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn typed_captures() {
+        let fields = [String::from(r"line=(?P<method>\w+) (?P<status>\d+)(?P<slow> slow)?")];
+        let rows = vec![
+            json!({"line": "GET 200"}),
+            json!({"line": "POST 500 slow"}),
+            json!({"line": "garbage"}),
+            json!({"line": 1}),
+        ];
+
+        assert_eq!(
+            Extract::parse(&fields).unwrap().apply(rows),
+            vec![
+                json!({"line": "GET 200", "method": "GET", "status": 200, "slow": null}),
+                json!({"line": "POST 500 slow", "method": "POST", "status": 500, "slow": " slow"}),
+                json!({"line": "garbage", "method": null, "status": null, "slow": null}),
+                json!({"line": 1, "method": null, "status": null, "slow": null}),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_extracts() {
+        for field in ["line", "line=(", r"line=(\d+)"] {
+            assert!(Extract::parse(&[String::from(field)]).is_err(), "{}", field);
+        }
+    }
+
+    #[test]
+    fn splits_and_trims() {
+        let fields = [String::from("tags=,"), String::from("path=::")];
+        let rows = vec![
+            json!({"tags": "a, b,,c ", "path": "x::y"}),
+            json!({"tags": ["a"], "path": ""}),
+        ];
+
+        assert_eq!(
+            Split::parse(&fields).unwrap().apply(rows),
+            vec![
+                json!({"tags": ["a", "b", "c"], "path": ["x", "y"]}),
+                json!({"tags": ["a"], "path": []}),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_splits() {
+        for field in ["tags", "tags=", "=,"] {
+            assert!(Split::parse(&[String::from(field)]).is_err(), "{}", field);
+        }
+    }
+}
@@ -1,115 +1,754 @@
-use super::Action;
+use std::{
+    cmp::Ordering,
+    collections::HashSet,
+    hash::{Hash, Hasher},
+};
 
-use crate::Result;
+use super::{as_integer, Action};
+
+use crate::{expr::Expr, path::FieldPath, time, Result};
 
 use serde_json::Value;
 
-type FilterExpr = (String, Primitive);
+#[derive(Debug)]
+pub struct FilterExpr<'a> {
+    // The left hand side as written, looked up as a key first
+    pub field: &'a str,
+    pub left: Expr<'a>,
+    pub operator: Operator,
+}
+
+impl<'a> FilterExpr<'a> {
+    // A key named like the whole left hand side, like `a/b`, takes precedence
+    // over evaluating it as an expression.
+    fn eval(&self, row: &Value) -> Option<Value> {
+        match row.get(self.field) {
+            Some(value) => Some(value.clone()),
+            None => self.left.eval(row),
+        }
+    }
+}
 
+#[derive(Debug)]
+pub enum Operator {
+    Equals(Primitive),
+    NotEquals(Primitive),
+    LessThan(Primitive),
+    LessThanOrEqual(Primitive),
+    GreaterThan(Primitive),
+    GreaterThanOrEqual(Primitive),
+    In(HashSet<Primitive>),
+    NotIn(HashSet<Primitive>),
+    Between(Primitive, Primitive),
+    NotBetween(Primitive, Primitive),
+}
+
+// Integers are kept as i128 so that every JSON integer (i64 or u64) fits.
+// Floats with an integral value are normalized to integers, which makes
+// `29`, `29.0` and `2.9e1` equal, including inside a HashSet.
+// Timestamps are millis since epoch, see `crate::time`.
 #[derive(Debug)]
 pub enum Primitive {
+    Null,
     Bool(bool),
     String(String),
-    Integer(u64),
+    Integer(i128),
     Float(f64),
+    Timestamp(i64),
 }
 
 impl Primitive {
+    // Literal grammar:
+    //   null, true, false
+    //   signed integers and floats, with optional exponent: -5, 1.5, 2e10
+    //   timestamps: 2026-10-01T00:00:00Z, 2026-10-01, 10/Oct/2026:13:55:36 -0700
+    //   quoted strings force string comparison: "123", 'abc'
+    //   anything else is an unquoted string
     fn parse(expr: &str) -> Self {
-        if expr == "true" || expr == "false" {
-            return Self::Bool(expr.parse().unwrap());
+        match expr {
+            "null" => return Self::Null,
+            "true" => return Self::Bool(true),
+            "false" => return Self::Bool(false),
+            _ => {}
         }
 
-        let mut dot_count = 0;
+        parse_number(expr)
+            .or_else(|| time::parse(expr).map(Self::Timestamp))
+            .unwrap_or_else(|| Self::String(String::from(expr)))
+    }
 
-        for b in expr.bytes() {
-            if b == b'.' {
-                dot_count += 1;
-                continue;
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Null => Some(Self::Null),
+            Value::Bool(b) => Some(Self::Bool(*b)),
+            Value::String(s) => Some(Self::String(s.clone())),
+            Value::Number(n) => {
+                if let Some(n) = n.as_i64() {
+                    Some(Self::Integer(n as i128))
+                } else if let Some(n) = n.as_u64() {
+                    Some(Self::Integer(n as i128))
+                } else {
+                    n.as_f64().map(Self::float)
+                }
             }
+            _ => None,
+        }
+    }
 
-            if dot_count > 1 {
-                return Self::String(String::from(expr));
-            }
+    fn float(n: f64) -> Self {
+        match as_integer(n) {
+            Some(n) => Self::Integer(n as i128),
+            None => Self::Float(n),
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Integer(n) => Some(*n as f64),
+            Self::Float(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    // Strings are parsed as dates, numbers are taken as epoch seconds or millis.
+    fn as_timestamp(&self) -> Option<i64> {
+        match self {
+            Self::Timestamp(t) => Some(*t),
+            Self::String(s) => time::parse(s),
+            _ => self.as_f64().map(time::from_epoch),
+        }
+    }
 
-            if b.is_ascii_digit() {
-                continue;
-            } else {
-                return Self::String(String::from(expr));
+    // Ordering used by comparison operators. Integers and floats compare by value,
+    // and anything compared with a timestamp is read as a timestamp. Other types
+    // only compare with themselves.
+    fn compare(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Self::Timestamp(_), _) | (_, Self::Timestamp(_)) => {
+                Some(self.as_timestamp()?.cmp(&other.as_timestamp()?))
             }
+            (Self::Bool(a), Self::Bool(b)) => Some(a.cmp(b)),
+            (Self::String(a), Self::String(b)) => Some(a.cmp(b)),
+            (Self::Integer(a), Self::Integer(b)) => Some(a.cmp(b)),
+            _ => self.as_f64()?.partial_cmp(&other.as_f64()?),
+        }
+    }
+}
+
+// Returns None if `expr` isn't a number literal. Words like `inf` or `NaN`,
+// which Rust would happily parse as f64, are not numbers here.
+fn parse_number(expr: &str) -> Option<Primitive> {
+    let unsigned = expr.strip_prefix(['+', '-']).unwrap_or(expr);
+    let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+        Some(i) => (&unsigned[..i], Some(&unsigned[i + 1..])),
+        None => (unsigned, None),
+    };
+    let (whole, fraction) = match mantissa.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (mantissa, None),
+    };
+
+    let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+
+    if whole.is_empty() && fraction.is_none_or(str::is_empty) {
+        return None;
+    }
+
+    if !all_digits(whole) || !fraction.is_none_or(all_digits) {
+        return None;
+    }
+
+    if let Some(exponent) = exponent {
+        let digits = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+
+        if digits.is_empty() || !all_digits(digits) {
+            return None;
         }
+    }
 
-        if dot_count == 0 {
-            return Self::Integer(expr.parse().unwrap());
+    if fraction.is_none() && exponent.is_none() {
+        if let Ok(n) = expr.parse::<i128>() {
+            return Some(Primitive::Integer(n));
         }
+    }
+
+    // Integers overflowing i128 end up here too, as an approximate float.
+    expr.parse::<f64>().ok().map(Primitive::float)
+}
 
-        return Self::Float(expr.parse().unwrap());
+// Floats are compared by their bits so that primitives can live in a HashSet.
+impl PartialEq for Primitive {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Null, Self::Null) => true,
+            (Self::Bool(a), Self::Bool(b)) => a == b,
+            (Self::String(a), Self::String(b)) => a == b,
+            (Self::Integer(a), Self::Integer(b)) => a == b,
+            (Self::Float(a), Self::Float(b)) => a.to_bits() == b.to_bits(),
+            (Self::Timestamp(a), Self::Timestamp(b)) => a == b,
+            _ => false,
+        }
     }
 }
 
-#[derive(Debug)]
-pub struct Filter {
-    pub filter_expr: Option<FilterExpr>,
-}
-
-impl Filter {
-    pub fn parse(expr: &Option<String>) -> Result<Self> {
-        if let Some(expr) = expr {
-            let parts: Vec<_> = expr.split("=").collect();
-
-            return match parts.as_slice() {
-                [] => Err("Invalid filter expression"),
-                [_] => Err("Invalid filter expression"),
-                [first, second] => Ok(Self {
-                    filter_expr: Some((String::from(*first), Primitive::parse(second))),
-                }),
-                _ => Err("Invalid filter expression"),
-            };
+impl Eq for Primitive {}
+
+impl Hash for Primitive {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+
+        match self {
+            Self::Null => {}
+            Self::Bool(b) => b.hash(state),
+            Self::String(s) => s.hash(state),
+            Self::Integer(n) => n.hash(state),
+            Self::Float(n) => n.to_bits().hash(state),
+            Self::Timestamp(t) => t.hash(state),
         }
+    }
+}
+
+// A row is kept when it matches every expression in `all`, and at least
+// one expression in `any` if there are any.
+#[derive(Debug)]
+pub struct Filter<'a> {
+    pub all: Vec<FilterExpr<'a>>,
+    pub any: Vec<FilterExpr<'a>>,
+}
+
+impl<'a> Filter<'a> {
+    // Supported expressions:
+    //   field=value, also !=, <, <=, > and >=
+    //   field [not] in (value1, value2, ...)
+    //   field [not] between low and high
+    // The left hand side can be any expression from `crate::expr`, like `abs(delta) > 5`.
+    pub fn parse(all: &'a [String], any: &'a [String]) -> Result<Self> {
+        Ok(Self {
+            all: all.iter().map(|expr| parse_expr(expr)).collect::<Result<_>>()?,
+            any: any.iter().map(|expr| parse_expr(expr)).collect::<Result<_>>()?,
+        })
+    }
 
-        Ok(Self { filter_expr: None })
+    fn keep(&self, value: &Value) -> bool {
+        self.all.iter().all(|expr| matches(value, expr))
+            && (self.any.is_empty() || self.any.iter().any(|expr| matches(value, expr)))
     }
 }
 
-impl Action for Filter {
+impl<'a> Action for Filter<'a> {
     fn apply(&self, values: Vec<Value>) -> Vec<Value> {
-        match &self.filter_expr {
-            Some(expr) => values
-                .into_iter()
-                .filter(|value| matches(value, expr))
-                .collect(),
-            None => values,
+        if self.all.is_empty() && self.any.is_empty() {
+            return values;
         }
+
+        values.into_iter().filter(|value| self.keep(value)).collect()
     }
 }
 
 fn matches(value: &Value, filter_expr: &FilterExpr) -> bool {
-    match value {
-        Value::Object(obj) => {
-            if let Some(inner) = obj.get(&filter_expr.0) {
-                match (inner, &filter_expr.1) {
-                    (Value::Bool(actual), Primitive::Bool(expected)) => *actual == *expected,
-                    (Value::String(actual), Primitive::String(expected)) => *actual == *expected,
-                    (Value::Number(actual), Primitive::Integer(expected)) => {
-                        if actual.is_u64() {
-                            actual.as_u64().unwrap() == *expected
-                        } else {
-                            false
-                        }
-                    }
-                    (Value::Number(actual), Primitive::Float(expected)) => {
-                        if actual.is_f64() {
-                            actual.as_f64().unwrap() == *expected
-                        } else {
-                            false
-                        }
-                    }
-                    _ => false,
-                }
-            } else {
-                false
+    let actual = match (filter_expr.eval(value), &filter_expr.operator) {
+        (Some(inner), _) => Primitive::from_value(&inner),
+        // Missing values are null for `not in`, so both are kept or dropped alike
+        (None, Operator::NotIn(_)) => Some(Primitive::Null),
+        (None, _) => return false,
+    };
+
+    let compare = |expected: &Primitive| actual.as_ref().and_then(|a| a.compare(expected));
+
+    match &filter_expr.operator {
+        Operator::Equals(expected) => actual.is_some_and(|actual| equals(&actual, expected)),
+        Operator::NotEquals(expected) => actual.is_some_and(|actual| !equals(&actual, expected)),
+        Operator::LessThan(expected) => compare(expected) == Some(Ordering::Less),
+        Operator::LessThanOrEqual(expected) => {
+            matches!(compare(expected), Some(Ordering::Less | Ordering::Equal))
+        }
+        Operator::GreaterThan(expected) => compare(expected) == Some(Ordering::Greater),
+        Operator::GreaterThanOrEqual(expected) => {
+            matches!(compare(expected), Some(Ordering::Greater | Ordering::Equal))
+        }
+        Operator::In(set) => actual.is_some_and(|actual| contains(set, &actual)),
+        Operator::NotIn(set) => !actual.is_some_and(|actual| contains(set, &actual)),
+        Operator::Between(low, high) => actual.is_some_and(|actual| between(&actual, low, high)),
+        Operator::NotBetween(low, high) => actual.is_some_and(|actual| outside(&actual, low, high)),
+    }
+}
+
+fn equals(actual: &Primitive, expected: &Primitive) -> bool {
+    match expected {
+        Primitive::Timestamp(_) => actual.compare(expected) == Some(Ordering::Equal),
+        _ => actual == expected,
+    }
+}
+
+fn contains(set: &HashSet<Primitive>, actual: &Primitive) -> bool {
+    set.contains(actual)
+        || actual
+            .as_timestamp()
+            .is_some_and(|t| set.contains(&Primitive::Timestamp(t)))
+}
+
+// Inclusive on both ends, like SQL.
+fn between(actual: &Primitive, low: &Primitive, high: &Primitive) -> bool {
+    matches!(
+        (actual.compare(low), actual.compare(high)),
+        (Some(Ordering::Greater | Ordering::Equal), Some(Ordering::Less | Ordering::Equal))
+    )
+}
+
+// Values that can't be compared with the bounds are neither between nor outside.
+fn outside(actual: &Primitive, low: &Primitive, high: &Primitive) -> bool {
+    match (actual.compare(low), actual.compare(high)) {
+        (Some(to_low), Some(to_high)) => to_low == Ordering::Less || to_high == Ordering::Greater,
+        _ => false,
+    }
+}
+
+fn parse_expr(expr: &str) -> Result<FilterExpr<'_>> {
+    let (left, mut lexer) = parse_left(expr)?;
+    let field = expr[..lexer.pos].trim();
+
+    let operator = match lexer.next_token()? {
+        Some(Token::Equals) => Operator::Equals(parse_value(&mut lexer)?),
+        Some(Token::NotEquals) => Operator::NotEquals(parse_value(&mut lexer)?),
+        Some(Token::LessThan) => Operator::LessThan(parse_value(&mut lexer)?),
+        Some(Token::LessThanOrEqual) => Operator::LessThanOrEqual(parse_value(&mut lexer)?),
+        Some(Token::GreaterThan) => Operator::GreaterThan(parse_value(&mut lexer)?),
+        Some(Token::GreaterThanOrEqual) => Operator::GreaterThanOrEqual(parse_value(&mut lexer)?),
+        Some(Token::Word(word)) if is_keyword(word, "not") => match lexer.next_token()? {
+            Some(Token::Word(word)) if is_keyword(word, "in") => {
+                Operator::NotIn(parse_list(&mut lexer)?)
+            }
+            Some(Token::Word(word)) if is_keyword(word, "between") => {
+                let (low, high) = parse_range(&mut lexer)?;
+                Operator::NotBetween(low, high)
             }
+            _ => return Err("Expected `in` or `between` after `not`"),
+        },
+        Some(Token::Word(word)) if is_keyword(word, "in") => Operator::In(parse_list(&mut lexer)?),
+        Some(Token::Word(word)) if is_keyword(word, "between") => {
+            let (low, high) = parse_range(&mut lexer)?;
+            Operator::Between(low, high)
         }
+        _ => return Err("Invalid filter expression"),
+    };
+
+    if !lexer.rest().trim().is_empty() {
+        return Err("Unexpected input at end of filter expression");
+    }
+
+    Ok(FilterExpr {
+        field,
+        left,
+        operator,
+    })
+}
+
+// Parses the left hand side, returning it with a lexer at the operator. Field
+// names that aren't an expression followed by an operator, like `first name`,
+// `x:y` or `2xx`, are taken as they are, as long as they hold no `(`.
+fn parse_left(expr: &str) -> Result<(Expr<'_>, Lexer<'_>)> {
+    let error = match Expr::parse_prefix(expr) {
+        Ok((left, consumed)) => {
+            let lexer = Lexer {
+                input: expr,
+                pos: consumed,
+            };
+
+            if at_operator(lexer) {
+                return Ok((left, lexer));
+            }
+
+            "Invalid filter expression"
+        }
+        Err(err) => err,
+    };
+
+    let mut lexer = Lexer {
+        input: expr,
+        pos: 0,
+    };
+
+    while !at_operator(lexer) {
+        if lexer.next_token().ok().flatten().is_none() {
+            return Err(error);
+        }
+    }
+
+    let field = expr[..lexer.pos].trim();
+
+    if field.is_empty() || field.contains('(') {
+        return Err(error);
+    }
+
+    Ok((Expr::Path(FieldPath::parse(field)?), lexer))
+}
+
+fn at_operator(mut lexer: Lexer) -> bool {
+    match lexer.next_token() {
+        Ok(Some(Token::Word(word))) => ["not", "in", "between"]
+            .iter()
+            .any(|keyword| is_keyword(word, keyword)),
+        Ok(Some(token)) => !matches!(
+            token,
+            Token::Quoted(_) | Token::LeftParen | Token::RightParen | Token::Comma
+        ),
         _ => false,
     }
 }
+
+// Value on the right of a comparison. Unless quoted or a `now()` expression,
+// everything after the operator is the value, so unquoted values can contain spaces.
+fn parse_value(lexer: &mut Lexer) -> Result<Primitive> {
+    let rest = lexer.rest().trim_start();
+
+    if rest.starts_with(['"', '\'']) || is_now(rest) {
+        return parse_literal(lexer);
+    }
+
+    Ok(Primitive::parse(lexer.take_rest().trim()))
+}
+
+// Parses `(value1, value2, ...)`
+fn parse_list(lexer: &mut Lexer) -> Result<HashSet<Primitive>> {
+    if lexer.next_token()? != Some(Token::LeftParen) {
+        return Err("Expected `(` after `in`");
+    }
+
+    let mut set = HashSet::new();
+
+    loop {
+        set.insert(parse_literal(lexer)?);
+
+        match lexer.next_token()? {
+            Some(Token::Comma) => continue,
+            Some(Token::RightParen) => return Ok(set),
+            _ => return Err("Expected `,` or `)` in list"),
+        }
+    }
+}
+
+// Parses `low and high`
+fn parse_range(lexer: &mut Lexer) -> Result<(Primitive, Primitive)> {
+    let low = parse_literal(lexer)?;
+
+    match lexer.next_token()? {
+        Some(Token::Word(word)) if is_keyword(word, "and") => {}
+        _ => return Err("Expected `and` in between expression"),
+    }
+
+    let high = parse_literal(lexer)?;
+
+    Ok((low, high))
+}
+
+fn parse_literal(lexer: &mut Lexer) -> Result<Primitive> {
+    let literal = match lexer.next_token()? {
+        Some(Token::Word(word)) if is_keyword(word, "now") => {
+            if lexer.next_token()? != Some(Token::LeftParen)
+                || lexer.next_token()? != Some(Token::RightParen)
+            {
+                return Err("Expected `now()`");
+            }
+
+            Primitive::Timestamp(time::now())
+        }
+        Some(Token::Word(word)) => Primitive::parse(word),
+        Some(Token::Quoted(s)) => return Ok(Primitive::String(s)),
+        _ => return Err("Expected a value"),
+    };
+
+    match literal {
+        Primitive::Timestamp(t) => match t.checked_add(parse_offsets(lexer)?) {
+            Some(t) => Ok(Primitive::Timestamp(t)),
+            None => Err(OFFSET_OUT_OF_RANGE),
+        },
+        literal => Ok(literal),
+    }
+}
+
+// Sum of any `+ duration` or `- duration` following a timestamp, like `now() - 1h`.
+fn parse_offsets(lexer: &mut Lexer) -> Result<i64> {
+    let mut total = 0;
+
+    loop {
+        let mut peek = *lexer;
+
+        let (sign, duration) = match peek.next_token()? {
+            Some(Token::Word("+")) => (1, peek.next_token()?),
+            Some(Token::Word("-")) => (-1, peek.next_token()?),
+            Some(Token::Word(word)) if word.starts_with('+') => (1, Some(Token::Word(&word[1..]))),
+            Some(Token::Word(word)) if word.starts_with('-') => (-1, Some(Token::Word(&word[1..]))),
+            _ => return Ok(total),
+        };
+
+        let duration = match duration {
+            Some(Token::Word(word)) => time::parse_duration(word),
+            _ => None,
+        };
+
+        match duration {
+            Some(duration) => {
+                total = (sign * duration)
+                    .checked_add(total)
+                    .ok_or(OFFSET_OUT_OF_RANGE)?
+            }
+            None => return Err("Expected a duration like `30s`, `5m`, `1h` or `2d`"),
+        }
+
+        *lexer = peek;
+    }
+}
+
+const OFFSET_OUT_OF_RANGE: &str = "Timestamp offset out of range";
+
+fn is_now(s: &str) -> bool {
+    s.get(..3).is_some_and(|word| is_keyword(word, "now"))
+        && s[3..].trim_start().starts_with('(')
+}
+
+fn is_keyword(word: &str, keyword: &str) -> bool {
+    word.eq_ignore_ascii_case(keyword)
+}
+
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    Word(&'a str),
+    Quoted(String),
+    Equals,
+    NotEquals,
+    LessThan,
+    LessThanOrEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+    LeftParen,
+    RightParen,
+    Comma,
+}
+
+#[derive(Clone, Copy)]
+struct Lexer<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn take_rest(&mut self) -> &'a str {
+        let rest = self.rest();
+        self.pos = self.input.len();
+        rest
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token<'a>>> {
+        let trimmed = self.rest().trim_start();
+        self.pos = self.input.len() - trimmed.len();
+
+        let c = match trimmed.chars().next() {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+
+        // Two character operators
+        let token = match trimmed.get(..2) {
+            Some("!=") => Some(Token::NotEquals),
+            Some("<=") => Some(Token::LessThanOrEqual),
+            Some(">=") => Some(Token::GreaterThanOrEqual),
+            _ => None,
+        };
+
+        if let Some(token) = token {
+            self.pos += 2;
+            return Ok(Some(token));
+        }
+
+        let token = match c {
+            '=' => Token::Equals,
+            '<' => Token::LessThan,
+            '>' => Token::GreaterThan,
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            ',' => Token::Comma,
+            '"' | '\'' => return self.quoted(c).map(Some),
+            _ => {
+                let len = trimmed
+                    .find(|c: char| c.is_whitespace() || "=!<>(),\"'".contains(c))
+                    .unwrap_or(trimmed.len());
+
+                if len == 0 {
+                    return Err("Unexpected character in filter expression");
+                }
+
+                self.pos += len;
+                return Ok(Some(Token::Word(&trimmed[..len])));
+            }
+        };
+
+        self.pos += c.len_utf8();
+        Ok(Some(token))
+    }
+
+    // Quoted string, with backslash escaping the next character.
+    fn quoted(&mut self, quote: char) -> Result<Token<'a>> {
+        let mut s = String::new();
+        let mut chars = self.rest().char_indices().skip(1);
+
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some((_, escaped)) => s.push(escaped),
+                    None => break,
+                },
+                c if c == quote => {
+                    self.pos += i + c.len_utf8();
+                    return Ok(Token::Quoted(s));
+                }
+                c => s.push(c),
+            }
+        }
+
+        Err("Unterminated string in filter expression")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn keep(filter: &str, row: Value) -> bool {
+        let all = vec![String::from(filter)];
+        Filter::parse(&all, &[]).unwrap().keep(&row)
+    }
+
+    #[test]
+    fn literal_types() {
+        assert_eq!(Primitive::parse("null"), Primitive::Null);
+        assert_eq!(Primitive::parse("true"), Primitive::Bool(true));
+        assert_eq!(Primitive::parse("-5"), Primitive::Integer(-5));
+        assert_eq!(Primitive::parse("2.9e1"), Primitive::Integer(29));
+        assert_eq!(Primitive::parse("1.5"), Primitive::Float(1.5));
+        assert_eq!(Primitive::parse("inf"), Primitive::String(String::from("inf")));
+        assert_eq!(Primitive::parse("1.2.3"), Primitive::String(String::from("1.2.3")));
+        assert_eq!(Primitive::parse("e5"), Primitive::String(String::from("e5")));
+        assert_eq!(Primitive::parse("2026-10-01"), Primitive::Timestamp(1_790_812_800_000));
+    }
+
+    #[test]
+    fn numbers_compare_by_value() {
+        assert!(keep("age=29", json!({"age": 29.0})));
+        assert!(keep("age=29.0", json!({"age": 29})));
+        assert!(keep("big=18446744073709551615", json!({"big": u64::MAX})));
+        assert!(!keep("age=29", json!({"age": "29"})));
+        assert!(keep("id=\"123\"", json!({"id": "123"})));
+        assert!(!keep("id=\"123\"", json!({"id": 123})));
+    }
+
+    #[test]
+    fn in_list() {
+        assert!(keep("status in (200, 204)", json!({"status": 204})));
+        assert!(!keep("status in (200, 204)", json!({"status": 500})));
+        assert!(!keep("status in (200, 204)", json!({})));
+        assert!(keep("name IN ('a b', c)", json!({"name": "a b"})));
+    }
+
+    #[test]
+    fn not_in_treats_missing_as_null() {
+        assert!(keep("status not in (200)", json!({"status": 500})));
+        assert!(keep("status not in (200)", json!({"status": null})));
+        assert!(keep("status not in (200)", json!({})));
+        assert!(!keep("status not in (200, null)", json!({"status": null})));
+        assert!(!keep("status not in (200, null)", json!({})));
+    }
+
+    #[test]
+    fn between_is_inclusive() {
+        assert!(keep("age between 18 and 65", json!({"age": 18})));
+        assert!(keep("age between 18 and 65", json!({"age": 65})));
+        assert!(!keep("age between 18 and 65", json!({"age": 66})));
+        assert!(keep("age not between 18 and 65", json!({"age": 66})));
+        assert!(!keep("age not between 18 and 65", json!({"age": "old"})));
+    }
+
+    #[test]
+    fn all_and_any() {
+        let all = vec![String::from("age > 18"), String::from("age < 65")];
+        let any = vec![String::from("status=500"), String::from("status=503")];
+        let filter = Filter::parse(&all, &any).unwrap();
+
+        assert!(filter.keep(&json!({"age": 30, "status": 503})));
+        assert!(!filter.keep(&json!({"age": 30, "status": 200})));
+        assert!(!filter.keep(&json!({"age": 70, "status": 500})));
+
+        let filter = Filter::parse(&[], &any).unwrap();
+        assert!(filter.keep(&json!({"status": 500})));
+        assert!(!filter.keep(&json!({})));
+
+        let rows = vec![json!({"a": 1}), json!({})];
+        assert_eq!(Filter::parse(&[], &[]).unwrap().apply(rows.clone()), rows);
+    }
+
+    #[test]
+    fn field_names_that_are_not_expressions() {
+        assert!(keep("first name=Bob", json!({"first name": "Bob"})));
+        assert!(keep("x:y=1", json!({"x:y": 1})));
+        assert!(keep("2xx=5", json!({"2xx": 5})));
+        assert!(keep("first name not in (Al)", json!({"first name": "Bob"})));
+        assert!(!keep("first name=Bob", json!({"first": "Bob"})));
+    }
+
+    #[test]
+    fn whole_field_names_take_precedence() {
+        assert!(keep("a/b=1", json!({"a/b": 1, "a": 4, "b": 2})));
+        assert!(keep("a/b=2", json!({"a": 4, "b": 2})));
+        assert!(keep("`first name`=Bob", json!({"first name": "Bob"})));
+        assert!(keep("`in`=1", json!({"in": 1})));
+    }
+
+    #[test]
+    fn left_hand_side_expressions() {
+        assert!(keep("len(powers) > 1", json!({"powers": ["a", "b"]})));
+        assert!(keep("abs(delta) >= 5", json!({"delta": -7})));
+        assert!(keep("address.city=Paris", json!({"address": {"city": "Paris"}})));
+    }
+
+    #[test]
+    fn timestamp_offsets() {
+        let row = json!({"ts": "2026-10-01T00:00:00Z"});
+
+        assert!(keep("ts < now() + 1h - 30m", row.clone()));
+        assert!(keep("ts between 2026-10-01 - 1d and 2026-10-01 + 1h", row.clone()));
+        assert!(!keep("ts between 2026-10-01 + 1ms and 2026-10-02", row));
+    }
+
+    #[test]
+    fn timestamp_offsets_out_of_range() {
+        let huge = " + 999999999999999999ms".repeat(10);
+        let filter = format!("ts > now(){}", huge);
+        assert_eq!(Filter::parse(&[filter], &[]).err(), Some(OFFSET_OUT_OF_RANGE));
+
+        // The offsets sum up to i64::MAX, but not once added to the timestamp
+        let filter = format!("ts > now(){} + 223372036854775816ms", &huge[..23 * 9]);
+        assert_eq!(Filter::parse(&[filter], &[]).err(), Some(OFFSET_OUT_OF_RANGE));
+    }
+
+    #[test]
+    fn invalid_expressions() {
+        let invalid = [
+            "age not 5",
+            "status in 200",
+            "status in (200",
+            "age between 1 or 2",
+            "len(powers > 1",
+            "=5",
+            "age",
+        ];
+
+        for filter in invalid {
+            assert!(Filter::parse(&[String::from(filter)], &[]).is_err(), "{}", filter);
+        }
+    }
+}
@@ -13,14 +13,14 @@ impl<'a> Action for Flatten<'a> {
         }
 
         for field in self.fields {
-            flatten_values(&mut values, &field)
+            flatten_values(&mut values, field)
         }
 
         values
     }
 }
 
-fn flatten_values(values: &mut Vec<Value>, field: &String) {
+fn flatten_values(values: &mut [Value], field: &str) {
     for value in values.iter_mut() {
         if let Value::Object(value) = value {
             let inner = value.remove(field);
@@ -29,7 +29,7 @@ fn flatten_values(values: &mut Vec<Value>, field: &String) {
                 Some(Value::Object(inner)) => {
                     for (key, val) in inner.into_iter() {
                         let new_key = format!("{}.{}", field, key);
-                        value.insert(String::from(new_key), val);
+                        value.insert(new_key, val);
                     }
                 }
                 Some(val) => {
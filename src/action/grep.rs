@@ -0,0 +1,87 @@
+use regex::Regex;
+use serde_json::Value;
+
+use crate::Result;
+
+use super::Action;
+
+#[derive(Clone)]
+pub struct Grep {
+    pub pattern: Option<Regex>,
+}
+
+impl Grep {
+    pub fn new(pattern: &Option<String>) -> Result<Self> {
+        let pattern = match pattern {
+            Some(pattern) => Some(Regex::new(pattern).map_err(|_| "Invalid grep pattern")?),
+            None => None,
+        };
+
+        Ok(Self { pattern })
+    }
+}
+
+impl Action for Grep {
+    fn apply(&self, values: Vec<Value>) -> Vec<Value> {
+        match &self.pattern {
+            Some(pattern) => values
+                .into_iter()
+                .filter(|value| matches(value, pattern))
+                .collect(),
+            None => values,
+        }
+    }
+}
+
+// Whether any scalar, at any depth, matches the pattern.
+// Scalars are matched as they are shown in the table, strings without quotes.
+fn matches(value: &Value, pattern: &Regex) -> bool {
+    match value {
+        Value::Null => pattern.is_match("null"),
+        Value::Bool(b) => pattern.is_match(&b.to_string()),
+        Value::Number(n) => pattern.is_match(&n.to_string()),
+        Value::String(s) => pattern.is_match(s),
+        Value::Array(arr) => arr.iter().any(|value| matches(value, pattern)),
+        Value::Object(obj) => obj.values().any(|value| matches(value, pattern)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn grep(pattern: &str, rows: Vec<Value>) -> Vec<Value> {
+        Grep::new(&Some(String::from(pattern))).unwrap().apply(rows)
+    }
+
+    #[test]
+    fn matches_scalars_at_any_depth() {
+        let rows = vec![
+            json!({"a": {"b": ["x", "timeout"]}}),
+            json!({"a": "ok", "n": 504}),
+            json!({"a": null, "b": false}),
+        ];
+
+        assert_eq!(grep("time", rows.clone()), vec![rows[0].clone()]);
+        assert_eq!(grep("^50", rows.clone()), vec![rows[1].clone()]);
+        assert_eq!(grep("null|false", rows.clone()), vec![rows[2].clone()]);
+    }
+
+    #[test]
+    fn strings_match_without_quotes() {
+        assert_eq!(grep("^ok$", vec![json!({"a": "ok"})]).len(), 1);
+        assert!(grep("\"", vec![json!({"a": "ok"})]).is_empty());
+    }
+
+    #[test]
+    fn keys_dont_match() {
+        assert!(grep("status", vec![json!({"status": 200})]).is_empty());
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        assert!(Grep::new(&Some(String::from("("))).is_err());
+    }
+}
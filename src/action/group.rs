@@ -0,0 +1,361 @@
+use std::collections::{HashMap, HashSet};
+
+use serde_json::{Map, Value};
+
+use crate::{
+    expr::{self, Expr},
+    Result,
+};
+
+use super::{
+    as_integer,
+    distinct::Canonical,
+    sort::{compare, Collation},
+    Action,
+};
+
+// Replaces rows with one row per group, holding the group keys and the
+// aggregates. Without keys, all rows are aggregated into a single row.
+pub struct Group<'a> {
+    pub keys: Vec<(&'a str, Expr<'a>)>,
+    pub aggregates: Vec<Aggregate<'a>>,
+}
+
+pub struct Aggregate<'a> {
+    pub column: &'a str,
+    pub function: Function,
+    pub arg: Option<Expr<'a>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Function {
+    Count,
+    Sum,
+    Min,
+    Max,
+    Avg,
+    Distinct,
+    First,
+    Last,
+    Percentile(f64),
+}
+
+impl<'a> Group<'a> {
+    // Parses comma separated keys like `service,address.country` and
+    // aggregates like `count(), avg(latency), p99(latency) as slow`.
+    pub fn parse(group_by: &'a Option<String>, aggregates: &'a Option<String>) -> Result<Option<Self>> {
+        if group_by.is_none() && aggregates.is_none() {
+            return Ok(None);
+        }
+
+        let keys = match group_by {
+            Some(group_by) => expr::split_top_level(group_by)
+                .into_iter()
+                .map(|key| Ok((key.trim(), Expr::parse(key)?)))
+                .collect::<Result<_>>()?,
+            None => Vec::new(),
+        };
+
+        let aggregates = expr::split_top_level(aggregates.as_deref().unwrap_or("count()"))
+            .into_iter()
+            .map(|aggregate| Aggregate::parse(aggregate.trim()))
+            .collect::<Result<_>>()?;
+
+        Ok(Some(Self { keys, aggregates }))
+    }
+}
+
+impl<'a> Aggregate<'a> {
+    fn parse(aggregate: &'a str) -> Result<Self> {
+        let (call, column) = match aggregate.rsplit_once(" as ") {
+            Some((call, alias)) if !alias.contains(')') => (call.trim(), alias.trim()),
+            _ => (aggregate, aggregate),
+        };
+
+        let (name, arg) = call
+            .strip_suffix(')')
+            .and_then(|call| call.split_once('('))
+            .ok_or("Expected an aggregate like `count()` or `avg(field)`")?;
+
+        let function = Function::parse(name.trim())?;
+
+        let arg = match (function, arg.trim()) {
+            (Function::Count, "") => None,
+            (_, "") => return Err("Aggregate needs a field, only count() can be empty"),
+            (_, arg) => Some(Expr::parse(arg)?),
+        };
+
+        Ok(Self {
+            column,
+            function,
+            arg,
+        })
+    }
+}
+
+impl Function {
+    pub(super) fn parse(name: &str) -> Result<Self> {
+        match name {
+            "count" => Ok(Self::Count),
+            "sum" => Ok(Self::Sum),
+            "min" => Ok(Self::Min),
+            "max" => Ok(Self::Max),
+            "avg" | "mean" => Ok(Self::Avg),
+            "distinct" | "count_distinct" => Ok(Self::Distinct),
+            "first" => Ok(Self::First),
+            "last" => Ok(Self::Last),
+            "median" => Ok(Self::Percentile(50.0)),
+            _ => match name.strip_prefix('p').map(str::parse::<f64>) {
+                Some(Ok(p)) if (0.0..=100.0).contains(&p) => Ok(Self::Percentile(p)),
+                _ => Err("Unknown aggregate, expected count, sum, min, max, avg, distinct, first, last, median or p0 to p100"),
+            },
+        }
+    }
+
+    // Null and undefined values are ignored by all functions, except count()
+    // without a field which counts rows.
+    pub(super) fn apply(&self, values: &[Value], rows: usize) -> Value {
+        match self {
+            Self::Count => Value::from(rows),
+            Self::Sum => sum(values),
+            Self::Min => values
+                .iter()
+                .min_by(|a, b| compare(a, b, Collation::Binary))
+                .cloned()
+                .unwrap_or(Value::Null),
+            Self::Max => values
+                .iter()
+                .max_by(|a, b| compare(a, b, Collation::Binary))
+                .cloned()
+                .unwrap_or(Value::Null),
+            Self::Avg => {
+                let numbers = numbers(values);
+
+                if numbers.is_empty() {
+                    Value::Null
+                } else {
+                    number(numbers.iter().sum::<f64>() / numbers.len() as f64)
+                }
+            }
+            Self::Distinct => {
+                let distinct: HashSet<Canonical> = values.iter().map(Canonical).collect();
+                Value::from(distinct.len())
+            }
+            Self::First => values.first().cloned().unwrap_or(Value::Null),
+            Self::Last => values.last().cloned().unwrap_or(Value::Null),
+            Self::Percentile(p) => percentile(numbers(values), *p),
+        }
+    }
+}
+
+// Rows and aggregated values of a single group
+struct Bucket<'k> {
+    keys: &'k [Value],
+    rows: usize,
+    values: Vec<Vec<Value>>,
+}
+
+impl<'a> Action for Group<'a> {
+    fn apply(&self, values: Vec<Value>) -> Vec<Value> {
+        let keys: Vec<Vec<Value>> = values
+            .iter()
+            .map(|row| {
+                let keys = self.keys.iter();
+                keys.map(|(_, key)| key.eval(row).unwrap_or(Value::Null)).collect()
+            })
+            .collect();
+
+        // Groups keep the order in which they are first seen
+        let mut buckets: Vec<Bucket> = Vec::new();
+        let mut index: HashMap<Vec<Canonical>, usize> = HashMap::new();
+
+        for (row, keys) in values.iter().zip(keys.iter()) {
+            let i = *index
+                .entry(keys.iter().map(Canonical).collect())
+                .or_insert_with(|| {
+                    buckets.push(Bucket {
+                        keys,
+                        rows: 0,
+                        values: vec![Vec::new(); self.aggregates.len()],
+                    });
+                    buckets.len() - 1
+                });
+
+            let bucket = &mut buckets[i];
+            bucket.rows += 1;
+
+            for (aggregate, values) in self.aggregates.iter().zip(bucket.values.iter_mut()) {
+                if let Some(value) = aggregate.arg.as_ref().and_then(|arg| arg.eval(row)) {
+                    if !value.is_null() {
+                        values.push(value);
+                    }
+                }
+            }
+        }
+
+        buckets
+            .into_iter()
+            .map(|bucket| {
+                let mut row = Map::new();
+
+                for ((name, _), key) in self.keys.iter().zip(bucket.keys) {
+                    row.insert(String::from(*name), key.clone());
+                }
+
+                for (aggregate, values) in self.aggregates.iter().zip(bucket.values) {
+                    let rows = match aggregate.arg {
+                        Some(_) => values.len(),
+                        None => bucket.rows,
+                    };
+
+                    row.insert(
+                        String::from(aggregate.column),
+                        aggregate.function.apply(&values, rows),
+                    );
+                }
+
+                Value::Object(row)
+            })
+            .collect()
+    }
+}
+
+fn numbers(values: &[Value]) -> Vec<f64> {
+    values.iter().filter_map(Value::as_f64).collect()
+}
+
+// Integers are summed exactly, unless they overflow or a float shows up.
+fn sum(values: &[Value]) -> Value {
+    let mut exact: Option<i64> = Some(0);
+    let mut total = 0.0;
+    let mut any = false;
+
+    for value in values {
+        if let Value::Number(n) = value {
+            any = true;
+            total += n.as_f64().unwrap_or(0.0);
+            exact = exact.and_then(|sum| sum.checked_add(n.as_i64()?));
+        }
+    }
+
+    match (any, exact) {
+        (false, _) => Value::Null,
+        (true, Some(sum)) => Value::from(sum),
+        (true, None) => number(total),
+    }
+}
+
+// Linear interpolation between the closest ranks
+fn percentile(mut numbers: Vec<f64>, p: f64) -> Value {
+    if numbers.is_empty() {
+        return Value::Null;
+    }
+
+    numbers.sort_by(f64::total_cmp);
+
+    let rank = p / 100.0 * (numbers.len() - 1) as f64;
+    let low = numbers[rank.floor() as usize];
+    let high = numbers[rank.ceil() as usize];
+
+    number(low + (high - low) * rank.fract())
+}
+
+// Whole numbers are shown without a fraction
+pub(super) fn number(n: f64) -> Value {
+    match as_integer(n) {
+        Some(n) => Value::from(n),
+        None => Value::from(n),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn group(group_by: &str, aggregates: &str, rows: Vec<Value>) -> Vec<Value> {
+        let group_by = Some(String::from(group_by));
+        let aggregates = Some(String::from(aggregates));
+
+        Group::parse(&group_by, &aggregates)
+            .unwrap()
+            .unwrap()
+            .apply(rows)
+    }
+
+    #[test]
+    fn equal_keys_group_together() {
+        let rows = vec![
+            json!({"k": 1}),
+            json!({"k": 1.0}),
+            json!({"k": {"a": 1, "b": 2}}),
+            json!({"k": {"b": 2, "a": 1}}),
+            json!({"k": "1"}),
+        ];
+
+        assert_eq!(
+            group("k", "count() as n", rows),
+            vec![
+                json!({"k": 1, "n": 2}),
+                json!({"k": {"a": 1, "b": 2}, "n": 2}),
+                json!({"k": "1", "n": 1}),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_keys_group_as_null() {
+        let rows = vec![json!({"k": null, "v": 1}), json!({"v": 2})];
+
+        assert_eq!(
+            group("k", "sum(v) as total", rows),
+            vec![json!({"k": null, "total": 3})]
+        );
+    }
+
+    #[test]
+    fn sums() {
+        assert_eq!(sum(&[json!(1), json!(2), json!("x")]), json!(3));
+        assert_eq!(sum(&[json!(1), json!(0.5)]), json!(1.5));
+        assert_eq!(sum(&[json!("x")]), Value::Null);
+        assert_eq!(sum(&[]), Value::Null);
+    }
+
+    #[test]
+    fn sum_overflow_falls_back_to_floats() {
+        assert_eq!(
+            sum(&[json!(i64::MAX), json!(1)]),
+            json!(9.223372036854776e18)
+        );
+        assert_eq!(
+            sum(&[json!(u64::MAX), json!(-1)]),
+            json!(1.8446744073709552e19)
+        );
+    }
+
+    #[test]
+    fn percentiles() {
+        let numbers = vec![4.0, 1.0, 3.0, 2.0];
+
+        assert_eq!(percentile(numbers.clone(), 0.0), json!(1));
+        assert_eq!(percentile(numbers.clone(), 50.0), json!(2.5));
+        assert_eq!(percentile(numbers.clone(), 100.0), json!(4));
+        assert_eq!(percentile(vec![7.0], 99.0), json!(7));
+        assert_eq!(percentile(Vec::new(), 50.0), Value::Null);
+    }
+
+    #[test]
+    fn whole_numbers() {
+        assert_eq!(number(2.0), json!(2));
+        assert_eq!(number(2.5), json!(2.5));
+        assert_eq!(number(1e19), json!(1e19));
+    }
+
+    #[test]
+    fn invalid_aggregates() {
+        for aggregate in ["avg()", "p101(x)", "nope(x)", "count"] {
+            assert!(Aggregate::parse(aggregate).is_err(), "{}", aggregate);
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::{
+    args::JoinKind,
+    expr::{self, Expr},
+    Result,
+};
+
+use super::{distinct::Canonical, Action};
+
+// Joins rows with the rows of another file, matching keys by value. Rows with
+// a null or missing key never match.
+//   inner  a row for each matching pair
+//   left   like inner, and rows without a match are kept as is
+//   anti   only rows without a match
+// Fields of the right rows are merged in, prefixed when the name is taken.
+pub struct Join<'a> {
+    pub right: Vec<Value>,
+    pub keys: Vec<(Expr<'a>, Expr<'a>)>,
+    pub kind: JoinKind,
+    pub prefix: String,
+    // Right keys of each right row
+    right_keys: Vec<Option<Value>>,
+}
+
+impl<'a> Join<'a> {
+    // Parses keys like `user_id=id`, or `id` when named the same on both sides.
+    // Several keys are comma separated.
+    pub fn new(right: Vec<Value>, keys: &'a str, kind: JoinKind, prefix: String) -> Result<Self> {
+        let keys = expr::split_top_level(keys)
+            .into_iter()
+            .map(|key| {
+                let (left, right) = key.split_once('=').unwrap_or((key, key));
+                Ok((Expr::parse(left.trim())?, Expr::parse(right.trim())?))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let right_keys = right
+            .iter()
+            .map(|row| key(keys.iter().map(|(_, right)| right), row))
+            .collect();
+
+        Ok(Self {
+            right,
+            keys,
+            kind,
+            prefix,
+            right_keys,
+        })
+    }
+
+    fn merge(&self, left: &Value, right: &Value) -> Value {
+        let (Value::Object(left), Value::Object(right)) = (left, right) else {
+            return left.clone();
+        };
+
+        let mut row = left.clone();
+
+        for (name, value) in right {
+            match row.get(name) {
+                // A key named the same on both sides holds the same value
+                Some(existing) if existing == value => {}
+                Some(_) => {
+                    row.insert(format!("{}{}", self.prefix, name), value.clone());
+                }
+                None => {
+                    row.insert(name.clone(), value.clone());
+                }
+            }
+        }
+
+        Value::Object(row)
+    }
+}
+
+impl<'a> Action for Join<'a> {
+    fn apply(&self, values: Vec<Value>) -> Vec<Value> {
+        let mut index: HashMap<Canonical, Vec<usize>> = HashMap::new();
+
+        for (i, key) in self.right_keys.iter().enumerate() {
+            if let Some(key) = key {
+                index.entry(Canonical(key)).or_default().push(i);
+            }
+        }
+
+        let mut joined = Vec::new();
+
+        for row in values {
+            let key = key(self.keys.iter().map(|(left, _)| left), &row);
+            let matches = key.as_ref().and_then(|key| index.get(&Canonical(key)));
+
+            match (self.kind, matches) {
+                (JoinKind::Inner | JoinKind::Left, Some(matches)) => {
+                    joined.extend(matches.iter().map(|i| self.merge(&row, &self.right[*i])));
+                }
+                (JoinKind::Left | JoinKind::Anti, None) => joined.push(row),
+                _ => {}
+            }
+        }
+
+        joined
+    }
+}
+
+// Values of the key fields, or None when any of them is null or missing
+fn key<'e, 'a: 'e>(keys: impl Iterator<Item = &'e Expr<'a>>, row: &Value) -> Option<Value> {
+    let values = keys
+        .map(|key| key.eval(row).filter(|value| !value.is_null()))
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(Value::Array(values))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn join(kind: JoinKind, keys: &str, left: Vec<Value>) -> Vec<Value> {
+        let right = vec![
+            json!({"id": 1, "name": "a"}),
+            json!({"id": 1.0, "name": "b"}),
+            json!({"id": 2, "name": "c"}),
+            json!({"id": null, "name": "d"}),
+        ];
+
+        Join::new(right, keys, kind, String::from("right_"))
+            .unwrap()
+            .apply(left)
+    }
+
+    fn left() -> Vec<Value> {
+        vec![json!({"user": 1}), json!({"user": 3}), json!({"user": null})]
+    }
+
+    #[test]
+    fn inner_joins_every_match() {
+        assert_eq!(
+            join(JoinKind::Inner, "user=id", left()),
+            vec![
+                json!({"user": 1, "id": 1, "name": "a"}),
+                json!({"user": 1, "id": 1.0, "name": "b"}),
+            ]
+        );
+    }
+
+    #[test]
+    fn left_keeps_rows_without_a_match() {
+        assert_eq!(join(JoinKind::Left, "user=id", left()).len(), 4);
+        assert_eq!(join(JoinKind::Left, "user=id", left())[2], json!({"user": 3}));
+    }
+
+    #[test]
+    fn anti_keeps_only_rows_without_a_match() {
+        assert_eq!(
+            join(JoinKind::Anti, "user=id", left()),
+            vec![json!({"user": 3}), json!({"user": null})]
+        );
+    }
+
+    #[test]
+    fn taken_names_are_prefixed() {
+        let left = vec![json!({"id": 2, "name": "x"}), json!({"id": 1, "name": "a"})];
+
+        assert_eq!(
+            join(JoinKind::Inner, "id", left),
+            vec![
+                json!({"id": 2, "name": "x", "right_name": "c"}),
+                json!({"id": 1, "name": "a"}),
+                json!({"id": 1, "name": "a", "right_id": 1.0, "right_name": "b"}),
+            ]
+        );
+    }
+
+    #[test]
+    fn several_keys() {
+        let right = vec![json!({"a": 1, "b": 2, "v": true}), json!({"a": 1, "b": 3, "v": false})];
+        let join = Join::new(right, "a, b", JoinKind::Inner, String::new()).unwrap();
+
+        assert_eq!(
+            join.apply(vec![json!({"a": 1, "b": 3})]),
+            vec![json!({"a": 1, "b": 3, "v": false})]
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use serde_json::Value;
+
+use super::{sort::Top, Action};
+
+// Skips `offset` rows, then keeps the first `head` or the last `tail` rows.
+pub struct Limit {
+    pub offset: usize,
+    pub head: Option<usize>,
+    pub tail: Option<usize>,
+}
+
+impl Limit {
+    // The rows a preceding sort needs to produce for this limit.
+    pub fn top(&self) -> Option<Top> {
+        match (self.head, self.tail) {
+            (Some(head), _) => Some(Top::First(self.offset.saturating_add(head))),
+            (None, Some(tail)) if self.offset == 0 => Some(Top::Last(tail)),
+            _ => None,
+        }
+    }
+}
+
+impl Action for Limit {
+    fn apply(&self, mut values: Vec<Value>) -> Vec<Value> {
+        values.drain(..self.offset.min(values.len()));
+
+        if let Some(head) = self.head {
+            values.truncate(head);
+        }
+
+        if let Some(tail) = self.tail {
+            values.drain(..values.len().saturating_sub(tail));
+        }
+
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn huge_limits() {
+        let limit = Limit {
+            offset: 1,
+            head: Some(usize::MAX),
+            tail: None,
+        };
+
+        assert!(matches!(limit.top(), Some(Top::First(usize::MAX))));
+        assert_eq!(limit.apply(vec![json!(1), json!(2)]), vec![json!(2)]);
+    }
+}
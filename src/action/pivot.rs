@@ -0,0 +1,346 @@
+use std::collections::{HashMap, HashSet};
+
+use serde_json::{Map, Value};
+
+use crate::{expr::Expr, Result};
+
+use super::{distinct::Canonical, group::Function, Action};
+
+// Turns rows into one row per distinct `index`, with a column for each distinct
+// value of `columns`, holding the aggregate of `values` for that pair:
+//   {date, region, revenue} => {date, eu, us}
+// Columns keep the order their values are first seen in, and cells without any
+// row are null. Column names colliding with an index field or an earlier column,
+// like the number 1 and the string "1", get a suffix: `1_2`.
+pub struct Pivot<'a> {
+    pub index: Vec<(&'a str, Expr<'a>)>,
+    pub columns: Expr<'a>,
+    pub values: Option<Expr<'a>>,
+    pub function: Function,
+}
+
+// The inverse of a pivot, turns each column other than `index` into a row:
+//   {date, eu, us} => {date, variable, value}
+// Only `columns` are turned into rows when given.
+pub struct Unpivot<'a> {
+    pub index: Vec<(&'a str, Expr<'a>)>,
+    pub columns: Vec<(&'a str, Expr<'a>)>,
+    pub name: &'a str,
+    pub value: &'a str,
+}
+
+impl<'a> Pivot<'a> {
+    // Parses `index=date,columns=region,values=revenue,agg=sum`. Without values,
+    // rows are counted.
+    pub fn parse(spec: &'a Option<String>) -> Result<Option<Self>> {
+        let Some(spec) = spec else {
+            return Ok(None);
+        };
+
+        let mut index = Vec::new();
+        let mut columns = None;
+        let mut values = None;
+        let mut function = None;
+
+        for (option, fields) in parse_options(spec)? {
+            match (option, fields.as_slice()) {
+                ("index", _) => index = parse_fields(&fields)?,
+                ("columns", [field]) => columns = Some(Expr::parse(field)?),
+                ("values", [field]) => values = Some(Expr::parse(field)?),
+                ("agg", [name]) => function = Some(Function::parse(name)?),
+                ("columns" | "values" | "agg", _) => {
+                    return Err("Pivot columns, values and agg take a single field")
+                }
+                _ => return Err("Unknown pivot option, expected index, columns, values or agg"),
+            }
+        }
+
+        if index.is_empty() {
+            return Err("Pivot needs an index, like `index=date,columns=region`");
+        }
+
+        let function = match (&values, function) {
+            (_, Some(function)) => function,
+            (Some(_), None) => Function::Sum,
+            (None, None) => Function::Count,
+        };
+
+        if values.is_none() && !matches!(function, Function::Count) {
+            return Err("Pivot needs values for aggregates other than count");
+        }
+
+        Ok(Some(Self {
+            index,
+            columns: columns.ok_or("Pivot needs columns, like `index=date,columns=region`")?,
+            values,
+            function,
+        }))
+    }
+}
+
+impl<'a> Unpivot<'a> {
+    // Parses `index=date,columns=us,eu,name=region,value=revenue`.
+    // Only the index is required, and the names default to `variable` and `value`.
+    pub fn parse(spec: &'a Option<String>) -> Result<Option<Self>> {
+        let Some(spec) = spec else {
+            return Ok(None);
+        };
+
+        let mut unpivot = Self {
+            index: Vec::new(),
+            columns: Vec::new(),
+            name: "variable",
+            value: "value",
+        };
+
+        for (option, fields) in parse_options(spec)? {
+            match (option, fields.as_slice()) {
+                ("index", _) => unpivot.index = parse_fields(&fields)?,
+                ("columns", _) => unpivot.columns = parse_fields(&fields)?,
+                ("name", [name]) => unpivot.name = name,
+                ("value", [value]) => unpivot.value = value,
+                ("name" | "value", _) => return Err("Unpivot name and value take a single name"),
+                _ => return Err("Unknown unpivot option, expected index, columns, name or value"),
+            }
+        }
+
+        if unpivot.index.is_empty() {
+            return Err("Unpivot needs an index, like `index=date`");
+        }
+
+        Ok(Some(unpivot))
+    }
+}
+
+// Rows and values of one cell of the pivot table
+#[derive(Default)]
+struct Cell {
+    rows: usize,
+    values: Vec<Value>,
+}
+
+impl<'a> Action for Pivot<'a> {
+    fn apply(&self, values: Vec<Value>) -> Vec<Value> {
+        let keys: Vec<(Value, Value)> = values
+            .iter()
+            .map(|row| {
+                let index = self.index.iter().map(|(_, key)| eval(key, row)).collect();
+                (Value::Array(index), eval(&self.columns, row))
+            })
+            .collect();
+
+        // Distinct index and column values, in first-seen order
+        let mut rows: HashMap<Canonical, usize> = HashMap::new();
+        let mut columns: HashMap<Canonical, usize> = HashMap::new();
+        let mut index: Vec<&Value> = Vec::new();
+        let mut names: Vec<String> = Vec::new();
+        let mut taken: HashSet<String> = self
+            .index
+            .iter()
+            .map(|(name, _)| String::from(*name))
+            .collect();
+        let mut cells: HashMap<(usize, usize), Cell> = HashMap::new();
+
+        for ((key, column), row) in keys.iter().zip(values.iter()) {
+            let i = *rows.entry(Canonical(key)).or_insert_with(|| {
+                index.push(key);
+                index.len() - 1
+            });
+
+            let j = *columns.entry(Canonical(column)).or_insert_with(|| {
+                names.push(unique_name(column_name(column), &mut taken));
+                names.len() - 1
+            });
+
+            let cell = cells.entry((i, j)).or_default();
+            cell.rows += 1;
+
+            if let Some(value) = self.values.as_ref().and_then(|values| values.eval(row)) {
+                if !value.is_null() {
+                    cell.values.push(value);
+                }
+            }
+        }
+
+        index
+            .into_iter()
+            .enumerate()
+            .map(|(i, key)| {
+                let mut row = Map::new();
+
+                if let Value::Array(keys) = key {
+                    for ((name, _), key) in self.index.iter().zip(keys) {
+                        row.insert(String::from(*name), key.clone());
+                    }
+                }
+
+                for (j, name) in names.iter().enumerate() {
+                    let value = match cells.get(&(i, j)) {
+                        Some(cell) if self.values.is_some() => {
+                            self.function.apply(&cell.values, cell.values.len())
+                        }
+                        Some(cell) => self.function.apply(&[], cell.rows),
+                        None => Value::Null,
+                    };
+
+                    row.insert(name.clone(), value);
+                }
+
+                Value::Object(row)
+            })
+            .collect()
+    }
+}
+
+impl<'a> Action for Unpivot<'a> {
+    fn apply(&self, values: Vec<Value>) -> Vec<Value> {
+        let mut unpivoted = Vec::new();
+
+        for row in values.iter() {
+            let Value::Object(map) = row else {
+                continue;
+            };
+
+            let index: Vec<(&str, Value)> = self
+                .index
+                .iter()
+                .map(|(name, key)| (*name, eval(key, row)))
+                .collect();
+
+            // Every column other than the index, by default
+            let columns: Vec<(&str, Option<Value>)> = match self.columns.is_empty() {
+                true => map
+                    .iter()
+                    .filter(|(key, _)| !self.index.iter().any(|(name, _)| name == key))
+                    .map(|(key, value)| (key.as_str(), Some(value.clone())))
+                    .collect(),
+                false => self
+                    .columns
+                    .iter()
+                    .map(|(name, column)| (*name, column.eval(row)))
+                    .collect(),
+            };
+
+            for (name, value) in columns {
+                let Some(value) = value else {
+                    continue;
+                };
+
+                let mut row: Map<String, Value> = index
+                    .iter()
+                    .map(|(name, key)| (String::from(*name), key.clone()))
+                    .collect();
+
+                row.insert(String::from(self.name), Value::from(name));
+                row.insert(String::from(self.value), value);
+                unpivoted.push(Value::Object(row));
+            }
+        }
+
+        unpivoted
+    }
+}
+
+// Splits `a=x,y,b=z` into options with their comma separated fields.
+fn parse_options(spec: &str) -> Result<Vec<(&str, Vec<&str>)>> {
+    let mut options: Vec<(&str, Vec<&str>)> = Vec::new();
+
+    for part in spec.split(',').map(str::trim) {
+        match (part.split_once('='), options.last_mut()) {
+            (Some((option, field)), _) => options.push((option.trim(), vec![field.trim()])),
+            (None, Some((_, fields))) => fields.push(part),
+            (None, None) => return Err("Expected options like `index=date,columns=region`"),
+        }
+    }
+
+    if options.iter().flat_map(|(_, fields)| fields).any(|field| field.is_empty()) {
+        return Err("Empty field in pivot options");
+    }
+
+    Ok(options)
+}
+
+fn parse_fields<'a>(fields: &[&'a str]) -> Result<Vec<(&'a str, Expr<'a>)>> {
+    fields
+        .iter()
+        .map(|field| Ok((*field, Expr::parse(field)?)))
+        .collect()
+}
+
+fn eval(expr: &Expr, row: &Value) -> Value {
+    expr.eval(row).unwrap_or(Value::Null)
+}
+
+// Strings are used as is, other values as JSON
+fn column_name(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        _ => value.to_string(),
+    }
+}
+
+// The name, or when it is taken, the name with the first free suffix from `_2`
+pub(super) fn unique_name(name: String, taken: &mut HashSet<String>) -> String {
+    let name = match taken.contains(&name) {
+        false => name,
+        true => (2..)
+            .map(|i| format!("{}_{}", name, i))
+            .find(|candidate| !taken.contains(candidate))
+            .unwrap(),
+    };
+
+    taken.insert(name.clone());
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn pivot(spec: &str, rows: Vec<Value>) -> Vec<Value> {
+        let spec = Some(String::from(spec));
+        Pivot::parse(&spec).unwrap().unwrap().apply(rows)
+    }
+
+    #[test]
+    fn sums_values_per_cell() {
+        let rows = vec![
+            json!({"date": 1, "region": "eu", "revenue": 10}),
+            json!({"date": 1, "region": "us", "revenue": 5}),
+            json!({"date": 1, "region": "eu", "revenue": 2}),
+            json!({"date": 2, "region": "us", "revenue": 7}),
+        ];
+
+        assert_eq!(
+            pivot("index=date,columns=region,values=revenue", rows),
+            vec![
+                json!({"date": 1, "eu": 12, "us": 5}),
+                json!({"date": 2, "eu": null, "us": 7}),
+            ]
+        );
+    }
+
+    #[test]
+    fn colliding_column_names_get_a_suffix() {
+        let rows = vec![
+            json!({"date": 1, "c": 1}),
+            json!({"date": 1, "c": "1"}),
+            json!({"date": 1, "c": "1"}),
+            json!({"date": 1, "c": "date"}),
+        ];
+
+        assert_eq!(
+            pivot("index=date,columns=c", rows),
+            vec![json!({"date": 1, "1": 1, "1_2": 2, "date_2": 1})]
+        );
+    }
+
+    #[test]
+    fn invalid_specs() {
+        for spec in ["columns=c", "index=a", "index=a,columns=b,agg=avg", "index=a,nope=b"] {
+            assert!(Pivot::parse(&Some(String::from(spec))).is_err(), "{}", spec);
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use serde_json::Value;
+
+use crate::{time, Result};
+
+use super::Action;
+
+// Keeps a random sample of rows, in their original order:
+//   `1000` a uniform sample of 1000 rows, by reservoir sampling
+//   `1%`   each row with a probability of 1%, by Bernoulli sampling
+// The same seed gives the same sample of the same rows.
+pub struct Sample {
+    pub size: Size,
+    pub seed: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Size {
+    Rows(usize),
+    Percent(f64),
+}
+
+impl Sample {
+    pub fn parse(sample: &Option<String>, seed: Option<u64>) -> Result<Option<Self>> {
+        let Some(sample) = sample else {
+            return Ok(None);
+        };
+
+        let size = match sample.trim().strip_suffix('%') {
+            Some(percent) => match percent.trim().parse::<f64>() {
+                Ok(p) if p > 0.0 && p <= 100.0 => Size::Percent(p),
+                _ => return Err("Sample percentage should be above 0% and at most 100%"),
+            },
+            None => Size::Rows(
+                sample
+                    .trim()
+                    .parse()
+                    .map_err(|_| "Expected a sample size like `1000` or `1%`")?,
+            ),
+        };
+
+        Ok(Some(Self {
+            size,
+            seed: seed.unwrap_or_else(|| time::now() as u64),
+        }))
+    }
+}
+
+impl Action for Sample {
+    fn apply(&self, values: Vec<Value>) -> Vec<Value> {
+        let mut rng = SplitMix64(self.seed);
+
+        match self.size {
+            Size::Percent(p) => values
+                .into_iter()
+                .filter(|_| rng.next_f64() * 100.0 < p)
+                .collect(),
+            Size::Rows(n) => {
+                // Algorithm R, each row replaces a kept one with probability n / seen.
+                // The size comes from the user and can be huge, unlike the rows.
+                let mut reservoir: Vec<(usize, Value)> = Vec::with_capacity(n.min(values.len()));
+
+                for (i, row) in values.into_iter().enumerate() {
+                    if i < n {
+                        reservoir.push((i, row));
+                    } else {
+                        let j = rng.below(i as u64 + 1) as usize;
+
+                        if j < n {
+                            reservoir[j] = (i, row);
+                        }
+                    }
+                }
+
+                reservoir.sort_unstable_by_key(|(i, _)| *i);
+                reservoir.into_iter().map(|(_, row)| row).collect()
+            }
+        }
+    }
+}
+
+// Small and fast pseudo random generator, good enough for sampling.
+// https://prng.di.unimi.it/splitmix64.c
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // Uniform in [0, n), by multiply and shift rather than modulo
+    fn below(&mut self, n: u64) -> u64 {
+        ((self.next() as u128 * n as u128) >> 64) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn sample(size: &str, seed: u64, rows: usize) -> Vec<Value> {
+        let rows = (0..rows).map(|i| json!(i)).collect();
+
+        Sample::parse(&Some(String::from(size)), Some(seed))
+            .unwrap()
+            .unwrap()
+            .apply(rows)
+    }
+
+    #[test]
+    fn rows_in_original_order() {
+        let sampled = sample("10", 7, 1000);
+
+        assert_eq!(sampled.len(), 10);
+        assert!(sampled.windows(2).all(|w| w[0].as_u64() < w[1].as_u64()));
+    }
+
+    #[test]
+    fn same_seed_same_sample() {
+        assert_eq!(sample("10", 7, 1000), sample("10", 7, 1000));
+        assert_ne!(sample("10", 7, 1000), sample("10", 8, 1000));
+        assert_eq!(sample("5%", 7, 1000), sample("5%", 7, 1000));
+    }
+
+    #[test]
+    fn sizes_larger_than_the_rows() {
+        assert_eq!(sample("10000000000000", 1, 3), vec![json!(0), json!(1), json!(2)]);
+        assert_eq!(sample("100%", 1, 3).len(), 3);
+        assert!(sample("0", 1, 3).is_empty());
+    }
+
+    #[test]
+    fn percent_is_about_right() {
+        let len = sample("10%", 3, 10_000).len();
+        assert!((800..1200).contains(&len), "{}", len);
+    }
+
+    #[test]
+    fn invalid_sizes() {
+        for size in ["0%", "101%", "-1", "ten", "1.5"] {
+            assert!(Sample::parse(&Some(String::from(size)), None).is_err(), "{}", size);
+        }
+    }
+
+    #[test]
+    fn below_stays_in_range() {
+        let mut rng = SplitMix64(1);
+        assert!((0..1000).all(|_| rng.below(3) < 3));
+    }
+}
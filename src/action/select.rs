@@ -17,7 +17,7 @@ pub struct Select<'a> {
 
 impl<'a> Select<'a> {
     pub fn new(path_str: &'a str, mode: SelectMode) -> Result<Select<'a>> {
-        let path = FieldPath::parse(&path_str)?;
+        let path = FieldPath::parse(path_str)?;
 
         Ok(Select { path, mode })
     }
@@ -47,14 +47,14 @@ fn select(values: Vec<Value>, path: &FieldPath) -> Vec<Value> {
         .collect()
 }
 
-fn select_append(values: &mut Vec<Value>, path: &FieldPath) {
+fn select_append(values: &mut [Value], path: &FieldPath) {
     values
-        .into_iter()
-        .for_each(|value| select_and_append_from_value(value, &path));
+        .iter_mut()
+        .for_each(|value| select_and_append_from_value(value, path));
 }
 
 fn select_and_append_from_value(value: &mut Value, path: &FieldPath) {
-    let selected = select_from_value(&value, &path.selectors);
+    let selected = select_from_value(value, &path.selectors);
 
     match value {
         Value::Null => todo!(),
@@ -68,7 +68,7 @@ fn select_and_append_from_value(value: &mut Value, path: &FieldPath) {
     }
 }
 
-fn select_from_value<'a>(value: &Value, path: &[Selector]) -> Value {
+fn select_from_value(value: &Value, path: &[Selector]) -> Value {
     if let Some(selector) = path.first() {
         match value {
             Value::Array(arr) => select_from_value(&select_from_array(arr, selector), &path[1..]),
@@ -80,7 +80,7 @@ fn select_from_value<'a>(value: &Value, path: &[Selector]) -> Value {
     }
 }
 
-fn select_from_array(arr: &Vec<Value>, selector: &Selector) -> Value {
+fn select_from_array(arr: &[Value], selector: &Selector) -> Value {
     match selector {
         Selector::Field(field) => {
             exit_with_error(&format!("Can't select field {} from array", field))
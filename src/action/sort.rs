@@ -1,54 +1,523 @@
-use std::cmp::Ordering;
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+};
 
-use serde_json::Value;
+use serde_json::{Number, Value};
+
+use crate::{
+    args::Nulls,
+    expr::{self, Expr},
+    time, Result,
+};
 
 use super::Action;
 
+mod collation;
+
+pub use collation::Collation;
+
 pub struct Sort<'a> {
-    pub field: &'a Option<String>,
+    pub keys: Vec<SortKey<'a>>,
+    pub top: Option<Top>,
 }
 
-impl<'a> Action for Sort<'a> {
-    fn apply(&self, mut values: Vec<Value>) -> Vec<Value> {
-        if let Some(field) = &self.field {
-            values.sort_by(|val1, val2| match (val1, val2) {
-                (Value::Object(val1), Value::Object(val2)) => {
-                    match (val1.get(field), val2.get(field)) {
-                        (Some(a), Some(b)) => compare(a, b),
-                        (Some(_), None) => Ordering::Greater,
-                        (None, Some(_)) => Ordering::Less,
-                        _ => Ordering::Equal,
-                    }
+// Only the first or last rows in sorted order are needed, as with `--head` or
+// `--tail`. They are then found with a bounded heap instead of a full sort.
+#[derive(Debug, Clone, Copy)]
+pub enum Top {
+    First(usize),
+    Last(usize),
+}
+
+#[derive(Debug)]
+pub struct SortKey<'a> {
+    pub expr: Expr<'a>,
+    pub descending: bool,
+    pub nulls: Nulls,
+    pub collation: Collation,
+}
+
+impl<'a> Sort<'a> {
+    // Parses comma separated keys like `service,-latency,+ts,host:natural`.
+    // Keys are ascending unless prefixed with `-`, and can end with a collation.
+    // A key is any expression from `crate::expr`, like `len(powers)` or `abs(delta)`.
+    pub fn parse(fields: &'a Option<String>, nulls: Nulls) -> Result<Self> {
+        match fields {
+            Some(fields) => Self::parse_keys(fields, nulls),
+            None => Ok(Self {
+                keys: Vec::new(),
+                top: None,
+            }),
+        }
+    }
+
+    pub(super) fn parse_keys(fields: &'a str, nulls: Nulls) -> Result<Self> {
+        let keys = expr::split_top_level(fields)
+            .into_iter()
+            .map(|field| SortKey::parse(field.trim(), nulls))
+            .collect::<Result<_>>()?;
+
+        Ok(Self { keys, top: None })
+    }
+
+    // Keys of each row, with timestamps parsed once ahead of the comparisons.
+    // When a key has timestamp strings, its numbers are read as epoch seconds
+    // or millis, so that both order chronologically.
+    pub(super) fn keys(&self, rows: &[Value]) -> Vec<Vec<Option<Key>>> {
+        let mut keys: Vec<Vec<Option<Key>>> = rows
+            .iter()
+            .map(|row| self.keys.iter().map(|key| key.key(row)).collect())
+            .collect();
+
+        for i in 0..self.keys.len() {
+            let column = || keys.iter().filter_map(|row| row[i].as_ref());
+
+            if !column().any(|key| key.value.is_string() && key.time.is_some()) {
+                continue;
+            }
+
+            for key in keys.iter_mut().filter_map(|row| row[i].as_mut()) {
+                if let Value::Number(n) = &key.value {
+                    key.time = n.as_f64().map(time::from_epoch);
                 }
-                _ => Ordering::Equal,
-            });
+            }
         }
 
-        values
+        keys
+    }
+
+    pub(super) fn compare(&self, keys1: &[Option<Key>], keys2: &[Option<Key>]) -> Ordering {
+        self.keys
+            .iter()
+            .zip(keys1.iter().zip(keys2.iter()))
+            .map(|(key, (key1, key2))| key.compare(key1.as_ref(), key2.as_ref()))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
     }
 }
 
-fn compare(val1: &Value, val2: &Value) -> Ordering {
-    match (val1, val2) {
-        // Null
-        (Value::Null, _) => Ordering::Greater,
+impl<'a> SortKey<'a> {
+    fn parse(field: &'a str, nulls: Nulls) -> Result<Self> {
+        let (field, descending) = match field.as_bytes().first() {
+            Some(b'-') => (&field[1..], true),
+            Some(b'+') => (&field[1..], false),
+            _ => (field, false),
+        };
+
+        let (field, collation) = match field.rsplit_once(':') {
+            Some((field, collation)) if collation.bytes().all(|b| b.is_ascii_alphabetic()) => {
+                (field, Collation::parse(collation)?)
+            }
+            _ => (field, Collation::Binary),
+        };
+
+        if field.trim().is_empty() {
+            return Err("Empty sort field");
+        }
+
+        Ok(Self {
+            expr: Expr::parse(field)?,
+            descending,
+            nulls,
+            collation,
+        })
+    }
+
+    // Null and undefined values are treated the same
+    fn key(&self, row: &Value) -> Option<Key> {
+        let value = self.expr.eval(row).filter(|value| !value.is_null())?;
+
+        let time = match &value {
+            Value::String(s) => time::parse(s),
+            _ => None,
+        };
+
+        Some(Key { value, time })
+    }
+
+    // Null and missing values are placed first or last, whatever the direction.
+    fn compare(&self, val1: Option<&Key>, val2: Option<&Key>) -> Ordering {
+        let nulls_last = match self.nulls {
+            Nulls::First => Ordering::Greater,
+            Nulls::Last => Ordering::Less,
+        };
+
+        match (val1, val2) {
+            (Some(a), Some(b)) if self.descending => a.compare(b, self.collation).reverse(),
+            (Some(a), Some(b)) => a.compare(b, self.collation),
+            (Some(_), None) => nulls_last,
+            (None, Some(_)) => nulls_last.reverse(),
+            (None, None) => Ordering::Equal,
+        }
+    }
+}
+
+// A sort key value, with its instant when it's a timestamp
+#[derive(Debug)]
+pub struct Key {
+    value: Value,
+    time: Option<i64>,
+}
+
+impl Key {
+    // Like `compare`, without parsing timestamps again
+    fn compare(&self, other: &Self, collation: Collation) -> Ordering {
+        match (&self.value, &other.value, self.time, other.time) {
+            (_, _, Some(t1), Some(t2)) => t1.cmp(&t2),
+            (Value::String(_), Value::String(_), Some(_), None) => Ordering::Less,
+            (Value::String(_), Value::String(_), None, Some(_)) => Ordering::Greater,
+            (Value::String(s1), Value::String(s2), None, None) => collation.compare(s1, s2),
+            (val1, val2, _, _) => compare(val1, val2, collation),
+        }
+    }
+}
+
+impl<'a> Action for Sort<'a> {
+    // Keys are computed once per row, then rows are sorted by their keys.
+    fn apply(&self, values: Vec<Value>) -> Vec<Value> {
+        if self.keys.is_empty() {
+            return values;
+        }
+
+        let keys = self.keys(&values);
+
+        let ranked = values
+            .into_iter()
+            .zip(keys)
+            .enumerate()
+            .map(|(index, (row, keys))| Ranked {
+                keys,
+                index,
+                row,
+                sort: self,
+            });
+
+        let sorted = match self.top {
+            Some(Top::First(n)) => first(ranked, n),
+            Some(Top::Last(n)) => last(ranked, n),
+            None => {
+                let mut sorted: Vec<_> = ranked.collect();
+                sorted.sort();
+                sorted
+            }
+        };
+
+        sorted.into_iter().map(|ranked| ranked.row).collect()
+    }
+}
+
+// Row decorated with its sort keys. The original index breaks ties, which
+// keeps the sort stable when using a heap.
+struct Ranked<'s, 'a> {
+    keys: Vec<Option<Key>>,
+    index: usize,
+    row: Value,
+    sort: &'s Sort<'a>,
+}
+
+impl<'s, 'a> Ord for Ranked<'s, 'a> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sort
+            .compare(&self.keys, &other.keys)
+            .then_with(|| self.index.cmp(&other.index))
+    }
+}
+
+impl<'s, 'a> PartialOrd for Ranked<'s, 'a> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'s, 'a> PartialEq for Ranked<'s, 'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl<'s, 'a> Eq for Ranked<'s, 'a> {}
+
+// The `n` smallest items in order, keeping at most `n` in memory. The heap
+// grows with the items, as `n` comes from the user and can be huge.
+pub(super) fn first<T: Ord>(items: impl Iterator<Item = T>, n: usize) -> Vec<T> {
+    let mut heap = BinaryHeap::new();
 
-        // Bool
+    for item in items {
+        heap.push(item);
+
+        if heap.len() > n {
+            heap.pop();
+        }
+    }
+
+    heap.into_sorted_vec()
+}
+
+// The `n` largest items in order, keeping at most `n` in memory.
+fn last<T: Ord>(items: impl Iterator<Item = T>, n: usize) -> Vec<T> {
+    let mut sorted: Vec<T> = first(items.map(Reverse), n)
+        .into_iter()
+        .map(|Reverse(item)| item)
+        .collect();
+
+    sorted.reverse();
+    sorted
+}
+
+// Total order over JSON values. Values of different types order as
+//   null < bool < number < string < array < object
+// Strings that are timestamps order chronologically, before other strings,
+// which order by the collation.
+// Arrays compare element by element, objects entry by entry with keys sorted,
+// so key order within an object doesn't matter.
+pub fn compare(val1: &Value, val2: &Value, collation: Collation) -> Ordering {
+    match (val1, val2) {
         (Value::Bool(bool1), Value::Bool(bool2)) => bool1.cmp(bool2),
-        (Value::Bool(_), _) => Ordering::Less,
+        (Value::Number(n1), Value::Number(n2)) => compare_numbers(n1, n2),
+        (Value::String(s1), Value::String(s2)) => match (time::parse(s1), time::parse(s2)) {
+            (Some(t1), Some(t2)) => t1.cmp(&t2),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => collation.compare(s1, s2),
+        },
+        (Value::Array(arr1), Value::Array(arr2)) => {
+            compare_all(arr1.iter(), arr2.iter(), collation)
+        }
+        (Value::Object(obj1), Value::Object(obj2)) => {
+            let mut entries1: Vec<_> = obj1.iter().collect();
+            let mut entries2: Vec<_> = obj2.iter().collect();
+            entries1.sort_by_key(|(key, _)| *key);
+            entries2.sort_by_key(|(key, _)| *key);
+
+            for ((key1, val1), (key2, val2)) in entries1.iter().zip(entries2.iter()) {
+                let ordering = key1
+                    .cmp(key2)
+                    .then_with(|| compare(val1, val2, collation));
+
+                if ordering.is_ne() {
+                    return ordering;
+                }
+            }
+
+            entries1.len().cmp(&entries2.len())
+        }
+        _ => type_rank(val1).cmp(&type_rank(val2)),
+    }
+}
 
-        // Number
-        (Value::Number(n1), Value::Number(n2)) => match n1.as_f64().partial_cmp(&n2.as_f64()) {
-            Some(ordering) => ordering,
-            None => Ordering::Equal,
+// Integers compare exactly, anything else by float value.
+fn compare_numbers(n1: &Number, n2: &Number) -> Ordering {
+    match (n1.as_i64(), n2.as_i64()) {
+        (Some(i1), Some(i2)) => i1.cmp(&i2),
+        _ => match (n1.as_u64(), n2.as_u64()) {
+            (Some(u1), Some(u2)) => u1.cmp(&u2),
+            _ => n1
+                .as_f64()
+                .unwrap_or(0.0)
+                .total_cmp(&n2.as_f64().unwrap_or(0.0)),
         },
-        (Value::Number(_), _) => Ordering::Less,
+    }
+}
+
+fn compare_all<'v>(
+    mut vals1: impl Iterator<Item = &'v Value>,
+    mut vals2: impl Iterator<Item = &'v Value>,
+    collation: Collation,
+) -> Ordering {
+    loop {
+        match (vals1.next(), vals2.next()) {
+            (Some(val1), Some(val2)) => match compare(val1, val2, collation) {
+                Ordering::Equal => continue,
+                ordering => return ordering,
+            },
+            (Some(_), None) => return Ordering::Greater,
+            (None, Some(_)) => return Ordering::Less,
+            (None, None) => return Ordering::Equal,
+        }
+    }
+}
+
+fn type_rank(value: &Value) -> u8 {
+    match value {
+        Value::Null => 0,
+        Value::Bool(_) => 1,
+        Value::Number(_) => 2,
+        Value::String(_) => 3,
+        Value::Array(_) => 4,
+        Value::Object(_) => 5,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn sort(fields: &str, rows: Vec<Value>) -> Vec<Value> {
+        Sort::parse_keys(fields, Nulls::Last).unwrap().apply(rows)
+    }
+
+    #[test]
+    fn keys_with_directions() {
+        let rows = vec![
+            json!({"a": {"b": 1}, "n": "x"}),
+            json!({"a": {"b": 2}, "n": "y"}),
+            json!({"a": {"b": 1}, "n": "z"}),
+        ];
+
+        assert_eq!(
+            sort("a.b, -n", rows),
+            vec![
+                json!({"a": {"b": 1}, "n": "z"}),
+                json!({"a": {"b": 1}, "n": "x"}),
+                json!({"a": {"b": 2}, "n": "y"}),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_keys() {
+        for fields in ["", "a,", "-", "a:nope"] {
+            assert!(Sort::parse_keys(fields, Nulls::Last).is_err(), "{}", fields);
+        }
+    }
+
+    #[test]
+    fn nulls_first_or_last_in_both_directions() {
+        let rows = || vec![json!({"n": 2}), json!({}), json!({"n": null}), json!({"n": 1})];
+        let sort = |fields, nulls| Sort::parse_keys(fields, nulls).unwrap().apply(rows());
+
+        let expected = [json!({"n": 1}), json!({"n": 2}), json!({}), json!({"n": null})];
+        assert_eq!(sort("n", Nulls::Last), expected);
+
+        let expected = [json!({}), json!({"n": null}), json!({"n": 2}), json!({"n": 1})];
+        assert_eq!(sort("-n", Nulls::First), expected);
+    }
+
+    #[test]
+    fn mixed_types_have_a_total_order() {
+        let ordered = [
+            json!(false),
+            json!(true),
+            json!(-1.5),
+            json!(2),
+            json!(u64::MAX),
+            json!("a"),
+            json!("b"),
+            json!([1]),
+            json!([1, 0]),
+            json!({"a": 1}),
+            json!({"a": 1, "b": 0}),
+        ];
+
+        for (i, val1) in ordered.iter().enumerate() {
+            for (j, val2) in ordered.iter().enumerate() {
+                assert_eq!(compare(val1, val2, Collation::Binary), i.cmp(&j), "{} {}", val1, val2);
+            }
+        }
+    }
+
+    #[test]
+    fn objects_compare_regardless_of_key_order() {
+        let ordering = compare(&json!({"a": 1, "b": 2}), &json!({"b": 2, "a": 1}), Collation::Binary);
+        assert_eq!(ordering, Ordering::Equal);
+    }
+
+    #[test]
+    fn first_and_last() {
+        let items = [5, 1, 4, 2, 3];
+
+        assert_eq!(first(items.into_iter(), 2), [1, 2]);
+        assert_eq!(last(items.into_iter(), 2), [4, 5]);
+        assert!(first(items.into_iter(), 0).is_empty());
+        assert_eq!(first(items.into_iter(), usize::MAX), [1, 2, 3, 4, 5]);
+        assert_eq!(last(items.into_iter(), usize::MAX), [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn top_rows_keep_ties_stable() {
+        let rows = vec![
+            json!({"k": 1, "i": 0}),
+            json!({"k": 0, "i": 1}),
+            json!({"k": 1, "i": 2}),
+            json!({"k": 0, "i": 3}),
+        ];
+
+        let mut sort = Sort::parse_keys("k", Nulls::Last).unwrap();
+        sort.top = Some(Top::First(3));
+
+        assert_eq!(
+            sort.apply(rows),
+            vec![
+                json!({"k": 0, "i": 1}),
+                json!({"k": 0, "i": 3}),
+                json!({"k": 1, "i": 0}),
+            ]
+        );
+    }
+
+    #[test]
+    fn timestamps_sort_chronologically() {
+        let rows = vec![
+            json!({"ts": "2026-10-01T12:00:00+02:00"}),
+            json!({"ts": "2026-10-01T09:30:00Z"}),
+        ];
+
+        assert_eq!(
+            sort("ts", rows),
+            vec![
+                json!({"ts": "2026-10-01T09:30:00Z"}),
+                json!({"ts": "2026-10-01T12:00:00+02:00"}),
+            ]
+        );
+    }
+
+    #[test]
+    fn epoch_numbers_sort_among_timestamps() {
+        // 1790848800 is 2026-10-01T10:00:00Z
+        let rows = vec![
+            json!({"ts": "2026-10-01T10:00:05Z"}),
+            json!({"ts": 1790848800}),
+            json!({"ts": "2026-10-01T09:00:00Z"}),
+        ];
+
+        assert_eq!(
+            sort("-ts", rows),
+            vec![
+                json!({"ts": "2026-10-01T10:00:05Z"}),
+                json!({"ts": 1790848800}),
+                json!({"ts": "2026-10-01T09:00:00Z"}),
+            ]
+        );
+    }
+
+    #[test]
+    fn numbers_without_timestamps_stay_numbers() {
+        let rows = vec![json!({"n": "b"}), json!({"n": 20}), json!({"n": 3})];
+
+        assert_eq!(
+            sort("n", rows),
+            vec![json!({"n": 3}), json!({"n": 20}), json!({"n": "b"})]
+        );
+    }
 
-        // String
-        (Value::String(s1), Value::String(s2)) => s1.cmp(s2),
-        (Value::String(_), _) => Ordering::Less,
+    #[test]
+    fn timestamps_sort_before_other_strings() {
+        let rows = vec![
+            json!({"ts": "unknown"}),
+            json!({"ts": "2026-10-02"}),
+            json!({"ts": "2026-10-01"}),
+        ];
 
-        // Array or Object
-        (_, _) => Ordering::Equal,
+        assert_eq!(
+            sort("ts", rows),
+            vec![
+                json!({"ts": "2026-10-01"}),
+                json!({"ts": "2026-10-02"}),
+                json!({"ts": "unknown"}),
+            ]
+        );
     }
 }
@@ -0,0 +1,258 @@
+use std::cmp::Ordering;
+
+use crate::Result;
+
+// How strings compare within a sort key, selected with `field:collation`.
+#[derive(Debug, Clone, Copy)]
+pub enum Collation {
+    // Byte-wise, the default
+    Binary,
+    // Runs of digits compare as numbers: node-2 < node-10
+    Natural,
+    // Ignores case: apple < Banana < cherry
+    NoCase,
+    // Semantic versions: 1.9 < 1.10, 1.0.0-rc.1 < 1.0.0
+    Semver,
+}
+
+impl Collation {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "binary" => Ok(Self::Binary),
+            "natural" => Ok(Self::Natural),
+            "nocase" | "ci" => Ok(Self::NoCase),
+            "semver" | "version" => Ok(Self::Semver),
+            _ => Err("Unknown sort collation, expected natural, nocase or semver"),
+        }
+    }
+
+    // Strings equal under a collation fall back to byte-wise order,
+    // so that the order stays total and deterministic.
+    pub fn compare(&self, s1: &str, s2: &str) -> Ordering {
+        let ordering = match self {
+            Self::Binary => Ordering::Equal,
+            Self::Natural => natural(s1, s2),
+            Self::NoCase => s1
+                .chars()
+                .flat_map(char::to_lowercase)
+                .cmp(s2.chars().flat_map(char::to_lowercase)),
+            // Strings that aren't versions go after versions
+            Self::Semver => match (Version::parse(s1), Version::parse(s2)) {
+                (Some(v1), Some(v2)) => v1.cmp(&v2),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => natural(s1, s2),
+            },
+        };
+
+        ordering.then_with(|| s1.cmp(s2))
+    }
+}
+
+fn natural(s1: &str, s2: &str) -> Ordering {
+    let mut chunks1 = Chunks(s1);
+    let mut chunks2 = Chunks(s2);
+
+    loop {
+        let ordering = match (chunks1.next(), chunks2.next()) {
+            (Some(c1), Some(c2)) => match (is_digits(c1), is_digits(c2)) {
+                (true, true) => compare_digits(c1, c2),
+                _ => c1.cmp(c2),
+            },
+            (Some(_), None) => Ordering::Greater,
+            (None, Some(_)) => Ordering::Less,
+            (None, None) => return Ordering::Equal,
+        };
+
+        if ordering.is_ne() {
+            return ordering;
+        }
+    }
+}
+
+// Compares digit runs as numbers of any length, ignoring leading zeros.
+fn compare_digits(d1: &str, d2: &str) -> Ordering {
+    let d1 = d1.trim_start_matches('0');
+    let d2 = d2.trim_start_matches('0');
+
+    d1.len().cmp(&d2.len()).then_with(|| d1.cmp(d2))
+}
+
+fn is_digits(s: &str) -> bool {
+    s.bytes().all(|b| b.is_ascii_digit())
+}
+
+// Splits a string into alternating runs of digits and non digits.
+struct Chunks<'a>(&'a str);
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let first = self.0.chars().next()?;
+        let digits = first.is_ascii_digit();
+        let len = self
+            .0
+            .find(|c: char| c.is_ascii_digit() != digits)
+            .unwrap_or(self.0.len());
+
+        let (chunk, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(chunk)
+    }
+}
+
+// [v]MAJOR[.MINOR[.PATCH...]][-PRERELEASE][+BUILD], build metadata is ignored.
+// Missing components count as zero, so 1.2 == 1.2.0.
+#[derive(Debug)]
+struct Version<'a> {
+    numbers: Vec<u64>,
+    pre_release: Option<&'a str>,
+}
+
+impl<'a> Version<'a> {
+    fn parse(s: &'a str) -> Option<Self> {
+        let s = s.strip_prefix(['v', 'V']).unwrap_or(s);
+        let s = s.split_once('+').map_or(s, |(version, _build)| version);
+        let (core, pre_release) = match s.split_once('-') {
+            Some((core, pre_release)) => (core, Some(pre_release)),
+            None => (s, None),
+        };
+
+        let numbers = core
+            .split('.')
+            .map(|n| if is_digits(n) { n.parse().ok() } else { None })
+            .collect::<Option<Vec<u64>>>()?;
+
+        Some(Self {
+            numbers,
+            pre_release,
+        })
+    }
+}
+
+impl<'a> Ord for Version<'a> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let len = self.numbers.len().max(other.numbers.len());
+        let component = |v: &Self, i| v.numbers.get(i).copied().unwrap_or(0);
+
+        (0..len)
+            .map(|i| component(self, i).cmp(&component(other, i)))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| match (self.pre_release, other.pre_release) {
+                // A pre-release comes before its release
+                (Some(p1), Some(p2)) => compare_pre_release(p1, p2),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            })
+    }
+}
+
+impl<'a> PartialEq for Version<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl<'a> Eq for Version<'a> {}
+
+impl<'a> PartialOrd for Version<'a> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Dot separated identifiers, numeric ones compare as numbers and before others.
+fn compare_pre_release(p1: &str, p2: &str) -> Ordering {
+    let mut ids1 = p1.split('.');
+    let mut ids2 = p2.split('.');
+
+    loop {
+        let ordering = match (ids1.next(), ids2.next()) {
+            (Some(id1), Some(id2)) => match (is_digits(id1), is_digits(id2)) {
+                (true, true) => compare_digits(id1, id2),
+                (true, false) => Ordering::Less,
+                (false, true) => Ordering::Greater,
+                (false, false) => id1.cmp(id2),
+            },
+            (Some(_), None) => Ordering::Greater,
+            (None, Some(_)) => Ordering::Less,
+            (None, None) => return Ordering::Equal,
+        };
+
+        if ordering.is_ne() {
+            return ordering;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(collation: Collation, strings: &[&'static str]) -> Vec<&'static str> {
+        let mut strings = strings.to_vec();
+        strings.sort_by(|s1, s2| collation.compare(s1, s2));
+        strings
+    }
+
+    #[test]
+    fn natural_compares_digit_runs_as_numbers() {
+        assert_eq!(
+            sorted(Collation::Natural, &["node-10", "node-2", "node-1", "node"]),
+            ["node", "node-1", "node-2", "node-10"]
+        );
+        assert_eq!(
+            sorted(Collation::Natural, &["a010", "a9", "a10"]),
+            ["a9", "a010", "a10"]
+        );
+        assert_eq!(
+            natural("x99999999999999999999999", "x100000000000000000000000"),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn nocase_ignores_case() {
+        assert_eq!(
+            sorted(Collation::NoCase, &["cherry", "Banana", "apple"]),
+            ["apple", "Banana", "cherry"]
+        );
+        // Equal ignoring case, then byte-wise
+        assert_eq!(sorted(Collation::NoCase, &["a", "A"]), ["A", "a"]);
+    }
+
+    #[test]
+    fn semver_orders_versions() {
+        assert_eq!(
+            sorted(
+                Collation::Semver,
+                &["1.10.0", "v1.9", "1.0.0", "1.0.0-rc.1", "1.0.0-alpha", "1.0.0-rc.10"]
+            ),
+            ["1.0.0-alpha", "1.0.0-rc.1", "1.0.0-rc.10", "1.0.0", "v1.9", "1.10.0"]
+        );
+    }
+
+    #[test]
+    fn semver_puts_other_strings_last() {
+        assert_eq!(
+            sorted(Collation::Semver, &["latest", "2.0", "1.2.3+build.5"]),
+            ["1.2.3+build.5", "2.0", "latest"]
+        );
+    }
+
+    #[test]
+    fn semver_missing_components_are_zero() {
+        assert_eq!(Version::parse("1.2"), Version::parse("1.2.0"));
+        assert!(Version::parse("1.x").is_none());
+        assert!(Version::parse("").is_none());
+    }
+
+    #[test]
+    fn unknown_collation() {
+        assert!(Collation::parse("Natural").is_ok());
+        assert!(Collation::parse("nope").is_err());
+    }
+}
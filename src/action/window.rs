@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::{
+    args::Nulls,
+    expr::{self, Expr},
+    Result,
+};
+
+use super::{distinct::Canonical, group::number, sort::Sort, Action};
+
+// Adds columns computed over the other rows of a partition, in the order of
+// the partition, like `row_number() by session order by ts`. Rows keep their
+// order, so this applies after sorting.
+pub struct Window<'a> {
+    pub columns: Vec<WindowColumn<'a>>,
+}
+
+pub struct WindowColumn<'a> {
+    pub column: &'a str,
+    pub function: Function<'a>,
+    pub partition: Vec<Expr<'a>>,
+    // Without keys, rows keep their current order
+    pub order: Sort<'a>,
+}
+
+pub enum Function<'a> {
+    RowNumber,
+    // Rows with equal order keys have the same rank, with gaps after them
+    Rank,
+    CumSum(Expr<'a>),
+    // The value in the previous or next row of the partition, or null
+    Lag(Expr<'a>),
+    Lead(Expr<'a>),
+}
+
+impl<'a> Window<'a> {
+    pub fn parse(columns: &'a [String], nulls: Nulls) -> Result<Option<Self>> {
+        if columns.is_empty() {
+            return Ok(None);
+        }
+
+        let columns = columns
+            .iter()
+            .map(|column| WindowColumn::parse(column.trim(), nulls))
+            .collect::<Result<_>>()?;
+
+        Ok(Some(Self { columns }))
+    }
+}
+
+impl<'a> WindowColumn<'a> {
+    // Parses `function(arg) [by keys] [order by keys] [as name]`, where keys are
+    // comma separated. Order keys are the ones of --sort, like `-latency`.
+    fn parse(spec: &'a str, nulls: Nulls) -> Result<Self> {
+        let open = spec.find('(').ok_or(WINDOW_EXPECTED)?;
+        let close = closing_paren(spec, open).ok_or(WINDOW_EXPECTED)?;
+
+        let call = &spec[..=close];
+        let function = Function::parse(spec[..open].trim(), spec[open + 1..close].trim())?;
+
+        let rest = &spec[close + 1..];
+        let words: Vec<(usize, &str)> = rest
+            .split_whitespace()
+            .map(|word| (word.as_ptr() as usize - rest.as_ptr() as usize, word))
+            .collect();
+
+        let is = |i: usize, keyword: &str| {
+            words
+                .get(i)
+                .is_some_and(|(_, word)| word.eq_ignore_ascii_case(keyword))
+        };
+
+        let alias = (0..words.len()).rev().find(|&i| is(i, "as"));
+        let order = (0..words.len()).find(|&i| is(i, "order") && is(i + 1, "by"));
+
+        let (end, column) = match alias {
+            Some(i) if i + 2 == words.len() => (words[i].0, words[i + 1].1),
+            Some(_) => return Err("Expected a single name after `as` in window"),
+            None => (rest.len(), call.trim()),
+        };
+
+        // Partition keys come first, up to the order keys
+        let (partition_end, order) = match order {
+            Some(i) => (
+                words[i].0,
+                Sort::parse_keys(&rest[words[i + 1].0 + 2..end], nulls)?,
+            ),
+            None => (
+                end,
+                Sort {
+                    keys: Vec::new(),
+                    top: None,
+                },
+            ),
+        };
+
+        let partition = match words.first() {
+            Some((start, _)) if *start >= partition_end => Vec::new(),
+            Some((start, _)) if is(0, "by") => {
+                expr::split_top_level(&rest[start + 2..partition_end])
+                    .into_iter()
+                    .map(|key| Expr::parse(key.trim()))
+                    .collect::<Result<_>>()?
+            }
+            Some(_) => return Err(WINDOW_EXPECTED),
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            column,
+            function,
+            partition,
+            order,
+        })
+    }
+
+    // Values of the column for each row, in row order
+    fn eval(&self, rows: &[Value]) -> Vec<Value> {
+        let keys: Vec<Value> = rows
+            .iter()
+            .map(|row| {
+                let keys = self.partition.iter();
+                Value::Array(
+                    keys.map(|key| key.eval(row).unwrap_or(Value::Null))
+                        .collect(),
+                )
+            })
+            .collect();
+
+        let mut partitions: HashMap<Canonical, Vec<usize>> = HashMap::new();
+
+        for (i, key) in keys.iter().enumerate() {
+            partitions.entry(Canonical(key)).or_default().push(i);
+        }
+
+        let order = self.order.keys(rows);
+        let mut results = vec![Value::Null; rows.len()];
+
+        for mut partition in partitions.into_values() {
+            // Stable, so rows with equal keys keep their current order
+            partition.sort_by(|&a, &b| self.order.compare(&order[a], &order[b]));
+
+            let mut rank = 0;
+            let mut total = 0.0;
+
+            for (n, &i) in partition.iter().enumerate() {
+                results[i] = match &self.function {
+                    Function::RowNumber => Value::from(n + 1),
+                    Function::Rank => {
+                        if n == 0
+                            || self
+                                .order
+                                .compare(&order[partition[n - 1]], &order[i])
+                                .is_ne()
+                        {
+                            rank = n + 1;
+                        }
+
+                        Value::from(rank)
+                    }
+                    Function::CumSum(expr) => {
+                        total += expr.eval(&rows[i]).and_then(|x| x.as_f64()).unwrap_or(0.0);
+                        number(total)
+                    }
+                    Function::Lag(expr) => n
+                        .checked_sub(1)
+                        .and_then(|previous| expr.eval(&rows[partition[previous]]))
+                        .unwrap_or(Value::Null),
+                    Function::Lead(expr) => partition
+                        .get(n + 1)
+                        .and_then(|&next| expr.eval(&rows[next]))
+                        .unwrap_or(Value::Null),
+                };
+            }
+        }
+
+        results
+    }
+}
+
+impl<'a> Function<'a> {
+    fn parse(name: &str, arg: &'a str) -> Result<Self> {
+        match (name, arg) {
+            ("row_number", "") => Ok(Self::RowNumber),
+            ("rank", "") => Ok(Self::Rank),
+            ("row_number" | "rank", _) => Err("row_number() and rank() take no field"),
+            ("cumsum" | "lag" | "lead", "") => Err("cumsum, lag and lead need a field"),
+            ("cumsum", arg) => Ok(Self::CumSum(Expr::parse(arg)?)),
+            ("lag", arg) => Ok(Self::Lag(Expr::parse(arg)?)),
+            ("lead", arg) => Ok(Self::Lead(Expr::parse(arg)?)),
+            _ => Err("Unknown window function, expected row_number, rank, cumsum, lag or lead"),
+        }
+    }
+}
+
+impl<'a> Action for Window<'a> {
+    fn apply(&self, mut values: Vec<Value>) -> Vec<Value> {
+        for column in self.columns.iter() {
+            let results = column.eval(&values);
+
+            for (row, value) in values.iter_mut().zip(results) {
+                if let Value::Object(map) = row {
+                    map.insert(String::from(column.column), value);
+                }
+            }
+        }
+
+        values
+    }
+}
+
+const WINDOW_EXPECTED: &str = "Expected a window like `row_number() by session order by ts`";
+
+// Index of the parenthesis closing the one at `open`
+fn closing_paren(s: &str, open: usize) -> Option<usize> {
+    let mut depth = 0;
+
+    for (i, c) in s.char_indices().skip_while(|(i, _)| *i < open) {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+
+        if depth == 0 {
+            return Some(i);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    // The computed column of each row
+    fn window(spec: &str, rows: Vec<Value>) -> Vec<Value> {
+        let column = WindowColumn::parse(spec, Nulls::Last).unwrap();
+        let name = column.column;
+
+        Window { columns: vec![column] }
+            .apply(rows)
+            .into_iter()
+            .map(|row| row[name].clone())
+            .collect()
+    }
+
+    fn rows() -> Vec<Value> {
+        vec![
+            json!({"s": "a", "ts": 3, "v": 1}),
+            json!({"s": "b", "ts": 1, "v": 2}),
+            json!({"s": "a", "ts": 1, "v": 3}),
+            json!({"s": "a", "ts": 3, "v": 4}),
+        ]
+    }
+
+    #[test]
+    fn row_numbers_per_partition() {
+        assert_eq!(window("row_number() by s order by ts as n", rows()), [2, 1, 1, 3]);
+        assert_eq!(window("row_number() as n", rows()), [1, 2, 3, 4]);
+        assert_eq!(window("row_number() order by -ts as n", rows()), [1, 3, 4, 2]);
+    }
+
+    #[test]
+    fn ranks_have_gaps_after_ties() {
+        assert_eq!(window("rank() by s order by ts as r", rows()), [2, 1, 1, 2]);
+        assert_eq!(window("rank() order by ts as r", rows()), [3, 1, 1, 3]);
+    }
+
+    #[test]
+    fn cumulative_sums() {
+        assert_eq!(window("cumsum(v) by s order by ts as total", rows()), [4, 2, 3, 8]);
+        assert_eq!(
+            window("cumsum(v * 0.5) as total", rows()),
+            [json!(0.5), json!(1.5), json!(3), json!(5)]
+        );
+    }
+
+    #[test]
+    fn lag_and_lead() {
+        assert_eq!(
+            window("lag(v) by s order by ts as prev", rows()),
+            [json!(3), Value::Null, Value::Null, json!(1)]
+        );
+        assert_eq!(
+            window("lead(v) by s as next", rows()),
+            [json!(3), Value::Null, json!(4), Value::Null]
+        );
+    }
+
+    #[test]
+    fn default_name_is_the_call() {
+        let column = WindowColumn::parse("lag(v) by s", Nulls::Last).unwrap();
+        assert_eq!(column.column, "lag(v)");
+    }
+
+    #[test]
+    fn invalid_windows() {
+        let specs = [
+            "row_number", "rank(v)", "cumsum()", "nope()", "lag(v) s", "lag(v) as a b", "lag(v",
+        ];
+
+        for spec in specs {
+            assert!(WindowColumn::parse(spec, Nulls::Last).is_err(), "{}", spec);
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use serde_json::Value;
+
+use crate::{expr::Expr, Result};
+
+use super::Action;
+
+// Adds computed columns to rows, each from `name = expr`. Columns are added in
+// order, so later ones can use earlier ones. Undefined values are null.
+pub struct With<'a> {
+    pub columns: Vec<(&'a str, Expr<'a>)>,
+}
+
+impl<'a> With<'a> {
+    pub fn parse(columns: &'a [String]) -> Result<Self> {
+        let columns = columns
+            .iter()
+            .map(|column| {
+                let (name, expr) = column
+                    .split_once('=')
+                    .ok_or("Expected a computed column like `name = expr`")?;
+
+                if name.trim().is_empty() {
+                    return Err("Empty name for computed column");
+                }
+
+                Ok((name.trim(), Expr::parse(expr)?))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { columns })
+    }
+}
+
+impl<'a> Action for With<'a> {
+    fn apply(&self, mut values: Vec<Value>) -> Vec<Value> {
+        if self.columns.is_empty() {
+            return values;
+        }
+
+        for row in values.iter_mut() {
+            for (name, expr) in self.columns.iter() {
+                let value = expr.eval(row).unwrap_or(Value::Null);
+
+                if let Value::Object(map) = row {
+                    map.insert(String::from(*name), value);
+                }
+            }
+        }
+
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn later_columns_use_earlier_ones() {
+        let columns = [String::from("total = a + b"), String::from("double = total * 2")];
+        let with = With::parse(&columns).unwrap();
+
+        assert_eq!(
+            with.apply(vec![json!({"a": 1, "b": 2}), json!({"a": 1}), json!(3)]),
+            vec![
+                json!({"a": 1, "b": 2, "total": 3, "double": 6}),
+                json!({"a": 1, "total": null, "double": null}),
+                json!(3),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_columns() {
+        for column in ["total", " = 1", "a = 1 +"] {
+            assert!(With::parse(&[String::from(column)]).is_err(), "{}", column);
+        }
+    }
+}
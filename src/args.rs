@@ -24,14 +24,173 @@ pub struct Args {
     #[arg(long, value_enum, default_value_t=SelectMode::Auto)]
     pub select_mode: SelectMode,
 
-    // Support multiple fields and FieldPath later.
-    /// Field to sort by
-    #[arg(long, value_name = "FIELD")]
+    /// Comma separated fields to sort by, prefix with - for descending
+    #[arg(long, value_name = "FIELDS", allow_hyphen_values = true)]
     pub sort: Option<String>,
 
-    /// Filter expression
-    #[arg(long, value_name = "FIELD=VALUE")]
-    pub filter: Option<String>
+    /// Where to place null and missing values when sorting
+    #[arg(long, value_enum, default_value_t=Nulls::Last)]
+    pub nulls: Nulls,
+
+    /// Join rows with the rows of another file, like `--join users.csv on user_id=id`
+    #[arg(long, num_args = 3, value_names = ["FILE", "on", "KEYS"])]
+    pub join: Option<Vec<String>>,
+
+    /// Kind of join
+    #[arg(long, value_enum, default_value_t = JoinKind::Inner)]
+    pub join_kind: JoinKind,
+
+    /// Prefix for joined fields whose name is already taken [default: the file name, like `users.`]
+    #[arg(long, value_name = "PREFIX")]
+    pub join_prefix: Option<String>,
+
+    /// Add a column for each named group of a regex matched against a field, like `message=(?P<status>\d+)`. Repeat for more
+    #[arg(long, value_name = "FIELD=REGEX")]
+    pub extract: Vec<String>,
+
+    /// Split a string field into an array, like `tags=,`. Repeat for more
+    #[arg(long, value_name = "FIELD=DELIMITER")]
+    pub split: Vec<String>,
+
+    /// Add a computed column, like `latency_s = latency_ms / 1000`. Repeat for more
+    #[arg(long = "with", value_name = "COLUMN")]
+    pub with: Vec<String>,
+
+    /// Filter expression, repeat to require all of them. Field names can be quoted with backticks
+    #[arg(long, value_name = "EXPR")]
+    pub filter: Vec<String>,
+
+    /// Filter expression, repeat to require any of them
+    #[arg(long, visible_alias = "any", value_name = "EXPR")]
+    pub or_filter: Vec<String>,
+
+    /// Keep rows where any value, at any depth, matches the regex
+    #[arg(long, value_name = "PATTERN")]
+    pub grep: Option<String>,
+
+    /// Keep a random sample of N rows, or of a percentage of rows like `1%`
+    #[arg(long, value_name = "SIZE")]
+    pub sample: Option<String>,
+
+    /// Seed for --sample, to get the same sample every time
+    #[arg(long, value_name = "SEED", requires = "sample")]
+    pub seed: Option<u64>,
+
+    /// Round a timestamp field down to fixed intervals, like `ts:5m`. Counting by it fills empty buckets with zero
+    #[arg(long, value_name = "FIELD:INTERVAL")]
+    pub bucket: Option<String>,
+
+    /// Drop duplicate rows
+    #[arg(long)]
+    pub distinct: bool,
+
+    /// Comma separated fields, keep one row for each distinct value of them
+    #[arg(long, value_name = "FIELDS")]
+    pub distinct_on: Option<String>,
+
+    /// Which row to keep among duplicates
+    #[arg(long, value_enum, default_value_t = Keep::First)]
+    pub keep: Keep,
+
+    /// Add a count column with the number of duplicates of each kept row, or distinct_count if rows have a count field
+    #[arg(long)]
+    pub distinct_count: bool,
+
+    /// Count rows by each value of a field, most frequent first
+    #[arg(long, value_name = "FIELD", conflicts_with_all = ["group_by", "agg"])]
+    pub count_by: Option<String>,
+
+    /// Show only the N most frequent values with --count-by
+    #[arg(long, value_name = "N", requires = "count_by")]
+    pub top: Option<usize>,
+
+    /// Add a bar for each count with --count-by
+    #[arg(long, requires = "count_by")]
+    pub bar: bool,
+
+    /// Comma separated fields to group rows by
+    #[arg(long, value_name = "FIELDS")]
+    pub group_by: Option<String>,
+
+    /// Comma separated aggregates for each group, like `count(), avg(latency), p99(latency)`
+    #[arg(long, value_name = "AGGREGATES")]
+    pub agg: Option<String>,
+
+    /// Pivot rows into columns, like `index=date,columns=region,values=revenue,agg=sum`
+    #[arg(long, value_name = "SPEC")]
+    pub pivot: Option<String>,
+
+    /// Unpivot columns into rows, like `index=date,columns=us,eu,name=region,value=revenue`
+    #[arg(long, value_name = "SPEC", visible_alias = "melt")]
+    pub unpivot: Option<String>,
+
+    /// Add a column computed over a partition of rows, like `row_number() by session order by ts`. Repeat for more
+    #[arg(long, value_name = "FUNCTION")]
+    pub window: Vec<String>,
+
+    /// Show only the first N rows
+    #[arg(long, visible_alias = "limit", value_name = "N", conflicts_with = "tail")]
+    pub head: Option<usize>,
+
+    /// Show only the last N rows
+    #[arg(long, value_name = "N")]
+    pub tail: Option<usize>,
+
+    /// Skip the first N rows
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub offset: usize,
+
+    /// Compare with the rows of an older file, like `--diff old.json new.json`
+    #[arg(long, value_name = "OLD")]
+    pub diff: Option<String>,
+
+    /// Comma separated fields matching rows between files with --diff, rows match by position otherwise
+    #[arg(long, value_name = "FIELDS", requires = "diff")]
+    pub key: Option<String>,
+
+    /// Show unchanged rows too with --diff
+    #[arg(long, requires = "diff")]
+    pub all: bool,
+
+    /// Comma separated fields to mask, like `email,token`
+    #[arg(long, value_name = "FIELDS")]
+    pub mask: Option<String>,
+
+    /// Mask emails, JWTs, credit card numbers, IP addresses and AWS keys in any string
+    #[arg(long)]
+    pub mask_auto: bool,
+
+    /// How to mask values, hash keeps equal values equal so rows stay joinable
+    #[arg(long, value_enum, default_value_t = MaskMode::Partial)]
+    pub mask_mode: MaskMode,
+
+    /// Secret key of --mask-mode hash, to get the same hashes across runs. A random key is used otherwise
+    #[arg(long, value_name = "KEY")]
+    pub mask_key: Option<String>,
+
+    /// Show each row as a block of fields, one per line, like psql's expanded display
+    #[arg(long, visible_alias = "transpose")]
+    pub vertical: bool,
+
+    /// Show summary statistics for each column instead of the rows
+    #[arg(long)]
+    pub describe: bool,
+
+    /// Show the schema of the rows instead, as a tree or as JSON Schema
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "tree"
+    )]
+    pub schema: Option<SchemaFormat>,
+}
+
+#[derive(clap::ValueEnum, Debug, Copy, Clone)]
+pub enum SchemaFormat {
+    Tree,
+    JsonSchema,
 }
 
 #[derive(clap::ValueEnum, Debug, Copy, Clone)]
@@ -46,4 +205,41 @@ pub enum SelectMode {
     Only,
     Append,
     Auto,
-}
\ No newline at end of file
+}
+
+#[derive(clap::ValueEnum, Debug, Copy, Clone)]
+pub enum Nulls {
+    First,
+    Last,
+}
+
+#[derive(clap::ValueEnum, Debug, Copy, Clone)]
+pub enum Keep {
+    First,
+    Last,
+}
+
+#[derive(clap::ValueEnum, Debug, Copy, Clone)]
+pub enum JoinKind {
+    Inner,
+    Left,
+    Anti,
+}
+
+#[derive(clap::ValueEnum, Debug, Copy, Clone)]
+pub enum MaskMode {
+    Full,
+    Partial,
+    Hash,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sort_accepts_descending_keys() {
+        let args = Args::try_parse_from(["pretty", "data.json", "--sort", "-latency,id"]).unwrap();
+        assert_eq!(args.sort.as_deref(), Some("-latency,id"));
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use serde_json::Value;
+
+use crate::{
+    action::Canonical,
+    expr::{self, Expr},
+    table::{Cell, CellType, Header},
+    to_cell, Result,
+};
+
+// Compares old and new rows, matched by key or else by position. Rows are
+// marked as added (+), removed (-) or changed (~), and changed cells show
+// `old → new`. Rows follow the new order, with removed rows last.
+pub struct Diff<'a> {
+    pub keys: Vec<Expr<'a>>,
+    // Shows unchanged rows too
+    pub all: bool,
+}
+
+enum Change<'v> {
+    Added(&'v Value),
+    Removed(&'v Value),
+    Changed(&'v Value, &'v Value),
+    Unchanged(&'v Value),
+}
+
+impl<'a> Diff<'a> {
+    // Parses comma separated key fields like `id` or `region,host`
+    pub fn parse(keys: &'a Option<String>, all: bool) -> Result<Self> {
+        let keys = match keys {
+            Some(keys) => expr::split_top_level(keys)
+                .into_iter()
+                .map(|key| Expr::parse(key.trim()))
+                .collect::<Result<_>>()?,
+            None => Vec::new(),
+        };
+
+        Ok(Self { keys, all })
+    }
+
+    pub fn table(&self, old: &[Value], new: &[Value]) -> (Vec<Header>, Vec<Vec<Cell>>) {
+        let changes: Vec<Change> = self
+            .changes(old, new)
+            .into_iter()
+            .filter(|change| self.all || !matches!(change, Change::Unchanged(_)))
+            .collect();
+
+        // Columns of both sides, in first-seen order
+        let mut names: Vec<&str> = Vec::new();
+        let mut seen: HashSet<&str> = HashSet::new();
+
+        for row in new.iter().chain(old.iter()) {
+            if let Value::Object(map) = row {
+                for key in map.keys() {
+                    if seen.insert(key) {
+                        names.push(key);
+                    }
+                }
+            }
+        }
+
+        let rows: Vec<Vec<Cell>> = changes
+            .iter()
+            .map(|change| {
+                let marker = match change {
+                    Change::Added(_) => Cell::marker(String::from("+"), CellType::Added),
+                    Change::Removed(_) => Cell::marker(String::from("-"), CellType::Removed),
+                    Change::Changed(..) => Cell::marker(String::from("~"), CellType::Changed),
+                    Change::Unchanged(_) => Cell::marker(String::from(" "), CellType::Null),
+                };
+
+                let cells = names.iter().map(|name| match change {
+                    Change::Added(row) | Change::Removed(row) | Change::Unchanged(row) => {
+                        cell(row.get(name))
+                    }
+                    Change::Changed(old, new) => {
+                        let (old, new) = (old.get(name), new.get(name));
+
+                        match (old, new) {
+                            (Some(old), Some(new)) if Canonical(old) == Canonical(new) => {
+                                cell(Some(new))
+                            }
+                            (None, None) => cell(None),
+                            _ => Cell::changed(cell(old), cell(new)),
+                        }
+                    }
+                });
+
+                std::iter::once(marker).chain(cells).collect()
+            })
+            .collect();
+
+        let headers = std::iter::once("")
+            .chain(names)
+            .enumerate()
+            .map(|(i, name)| Header {
+                name: String::from(name),
+                max_width: rows
+                    .iter()
+                    .map(|row| row[i].text().chars().count())
+                    .chain(std::iter::once(name.chars().count()))
+                    .max()
+                    .unwrap_or(0),
+            })
+            .collect();
+
+        (headers, rows)
+    }
+
+    fn changes<'v>(&self, old: &'v [Value], new: &'v [Value]) -> Vec<Change<'v>> {
+        let compare = |old: &'v Value, new: &'v Value| match Canonical(old) == Canonical(new) {
+            true => Change::Unchanged(new),
+            false => Change::Changed(old, new),
+        };
+
+        if self.keys.is_empty() {
+            let matched = old.iter().zip(new.iter()).map(|(old, new)| compare(old, new));
+            let added = new.iter().skip(old.len()).map(Change::Added);
+            let removed = old.iter().skip(new.len()).map(Change::Removed);
+
+            return matched.chain(added).chain(removed).collect();
+        }
+
+        let old_keys: Vec<Value> = old.iter().map(|row| self.key(row)).collect();
+        let new_keys: Vec<Value> = new.iter().map(|row| self.key(row)).collect();
+
+        // Rows with the same key match in order
+        let mut index: HashMap<Canonical, VecDeque<usize>> = HashMap::new();
+
+        for (i, key) in old_keys.iter().enumerate() {
+            index.entry(Canonical(key)).or_default().push_back(i);
+        }
+
+        let mut matched = vec![false; old.len()];
+        let mut changes = Vec::new();
+
+        for (row, key) in new.iter().zip(new_keys.iter()) {
+            match index.get_mut(&Canonical(key)).and_then(VecDeque::pop_front) {
+                Some(i) => {
+                    matched[i] = true;
+                    changes.push(compare(&old[i], row));
+                }
+                None => changes.push(Change::Added(row)),
+            }
+        }
+
+        let removed = old
+            .iter()
+            .zip(matched)
+            .filter(|(_, matched)| !matched)
+            .map(|(row, _)| Change::Removed(row));
+
+        changes.extend(removed);
+        changes
+    }
+
+    fn key(&self, row: &Value) -> Value {
+        let keys = self.keys.iter();
+        Value::Array(keys.map(|key| key.eval(row).unwrap_or(Value::Null)).collect())
+    }
+}
+
+// Missing fields are empty
+fn cell(value: Option<&Value>) -> Cell {
+    value.map(to_cell).unwrap_or(Cell::string(String::from("")))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    // Cells as drawn, one string per row
+    fn diff(keys: Option<&str>, all: bool, old: Vec<Value>, new: Vec<Value>) -> Vec<String> {
+        let keys = keys.map(String::from);
+        let (headers, rows) = Diff::parse(&keys, all).unwrap().table(&old, &new);
+
+        let headers = headers.iter().map(|header| header.name.clone());
+        let rows = rows
+            .iter()
+            .map(|row| row.iter().map(Cell::text).collect::<Vec<_>>().join("|"));
+
+        std::iter::once(headers.collect::<Vec<_>>().join("|"))
+            .chain(rows)
+            .collect()
+    }
+
+    #[test]
+    fn by_key() {
+        let old = vec![
+            json!({"id": 1, "v": "a"}),
+            json!({"id": 2, "v": "b"}),
+            json!({"id": 3, "v": "c"}),
+        ];
+        let new = vec![
+            json!({"id": 3, "v": "c"}),
+            json!({"id": 1, "v": "x"}),
+            json!({"id": 4, "v": "d", "w": true}),
+        ];
+
+        assert_eq!(
+            diff(Some("id"), false, old, new),
+            ["|id|v|w", "~|1|\"a\" → \"x\"|", "+|4|\"d\"|true", "-|2|\"b\"|"]
+        );
+    }
+
+    #[test]
+    fn by_position() {
+        let old = vec![json!({"v": 1}), json!({"v": 2}), json!({"v": 3})];
+        let new = vec![json!({"v": 1.0}), json!({"v": 5})];
+
+        assert_eq!(diff(None, true, old, new), ["|v", " |1.0", "~|2 → 5", "-|3"]);
+    }
+
+    #[test]
+    fn duplicate_keys_match_in_order() {
+        let old = vec![json!({"k": 1, "v": 1}), json!({"k": 1, "v": 2})];
+        let new = vec![json!({"k": 1, "v": 1}), json!({"k": 1, "v": 3}), json!({"k": 1, "v": 4})];
+
+        assert_eq!(
+            diff(Some("k"), false, old, new),
+            ["|k|v", "~|1|2 → 3", "+|1|4"]
+        );
+    }
+
+    #[test]
+    fn added_and_removed_fields() {
+        let old = vec![json!({"id": 1, "gone": 1})];
+        let new = vec![json!({"id": 1, "new": 2})];
+
+        assert_eq!(diff(Some("id"), false, old, new), ["|id|new|gone", "~|1|→ 2|1 →"]);
+    }
+}
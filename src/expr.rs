@@ -0,0 +1,755 @@
+use std::ops::RangeInclusive;
+
+use serde_json::{Number, Value};
+
+use crate::{action::Canonical, path::FieldPath, Result};
+
+// Small expression language evaluated against a row, used by sort keys,
+// computed columns and the left hand side of filters:
+//   paths:        name, address.country, powers.[].0, `x request id`
+//   literals:     42, -1.5, 2e3, "text", true, false, null
+//   arithmetic:   + - * / % and parentheses, + also joins strings
+//   comparisons:  = (or ==) != < <= > >=, and, or, not
+//   conditionals: if(cond, then, else), case when cond then x ... else y end
+//   functions:    len(x), abs(x), lower(s), upper(s), substr(s, start, len), split(s, sep)
+//
+// Field names can contain `-`, so subtraction needs spaces around it: `a - b`.
+// Keywords like `and` or `case` need backticks when used as field names.
+#[derive(Debug)]
+pub enum Expr<'a> {
+    Literal(Value),
+    Path(FieldPath<'a>),
+    Negate(Box<Expr<'a>>),
+    Not(Box<Expr<'a>>),
+    Binary(BinaryOp, Box<Expr<'a>>, Box<Expr<'a>>),
+    Call(Function, Vec<Expr<'a>>),
+    // The first branch whose condition is true, else the fallback
+    Case(Vec<(Expr<'a>, Expr<'a>)>, Option<Box<Expr<'a>>>),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Function {
+    Len,
+    Abs,
+    Lower,
+    Upper,
+    Substr,
+    Split,
+}
+
+// Binding power of comparisons. Operators binding at most as tight, like
+// comparisons and `and`, are left out of `Expr::parse_prefix`.
+const COMPARISON_POWER: u8 = 5;
+
+impl<'a> Expr<'a> {
+    pub fn parse(input: &'a str) -> Result<Self> {
+        let mut parser = Parser {
+            lexer: Lexer { input, pos: 0 },
+        };
+
+        let expr = parser.expr(0)?;
+
+        if parser.lexer.next()?.is_some() {
+            return Err("Unexpected input at end of expression");
+        }
+
+        Ok(expr)
+    }
+
+    // Parses the longest expression without comparisons at the start of
+    // `input`, returning it with the number of bytes consumed. Filters parse
+    // their own comparison after it.
+    pub fn parse_prefix(input: &'a str) -> Result<(Self, usize)> {
+        let mut parser = Parser {
+            lexer: Lexer { input, pos: 0 },
+        };
+
+        let expr = parser.expr(COMPARISON_POWER)?;
+
+        Ok((expr, parser.lexer.pos))
+    }
+
+    // Evaluates the expression against a row. Returns None when the value is
+    // undefined, like a missing field or arithmetic on a string.
+    pub fn eval(&self, row: &Value) -> Option<Value> {
+        match self {
+            Self::Literal(value) => Some(value.clone()),
+            Self::Path(path) => path.get(row).cloned(),
+            Self::Negate(expr) => arithmetic(BinaryOp::Subtract, &Value::from(0), &expr.eval(row)?),
+            Self::Not(expr) => Some(Value::Bool(!expr.test(row))),
+            Self::Binary(BinaryOp::And, left, right) => {
+                Some(Value::Bool(left.test(row) && right.test(row)))
+            }
+            Self::Binary(BinaryOp::Or, left, right) => {
+                Some(Value::Bool(left.test(row) || right.test(row)))
+            }
+            Self::Binary(op, left, right) => op.apply(&left.eval(row)?, &right.eval(row)?),
+            Self::Call(function, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| arg.eval(row))
+                    .collect::<Option<Vec<_>>>()?;
+
+                function.call(&args)
+            }
+            Self::Case(branches, otherwise) => {
+                match branches.iter().find(|(condition, _)| condition.test(row)) {
+                    Some((_, then)) => then.eval(row),
+                    None => otherwise.as_ref().and_then(|otherwise| otherwise.eval(row)),
+                }
+            }
+        }
+    }
+
+    // Conditions hold only when they evaluate to true
+    fn test(&self, row: &Value) -> bool {
+        matches!(self.eval(row), Some(Value::Bool(true)))
+    }
+}
+
+impl BinaryOp {
+    // Binding power, higher binds tighter
+    fn binding_power(&self) -> u8 {
+        match self {
+            Self::Or => 1,
+            Self::And => 2,
+            Self::Equal
+            | Self::NotEqual
+            | Self::Less
+            | Self::LessOrEqual
+            | Self::Greater
+            | Self::GreaterOrEqual => COMPARISON_POWER,
+            Self::Add | Self::Subtract => 10,
+            Self::Multiply | Self::Divide | Self::Remainder => 20,
+        }
+    }
+
+    fn apply(&self, left: &Value, right: &Value) -> Option<Value> {
+        let ordering = || match (left, right) {
+            (Value::Number(n1), Value::Number(n2)) => match (n1.as_i64(), n2.as_i64()) {
+                (Some(i1), Some(i2)) => Some(i1.cmp(&i2)),
+                _ => n1.as_f64()?.partial_cmp(&n2.as_f64()?),
+            },
+            (Value::String(s1), Value::String(s2)) => Some(s1.cmp(s2)),
+            (Value::Bool(b1), Value::Bool(b2)) => Some(b1.cmp(b2)),
+            _ => None,
+        };
+
+        let result = match self {
+            Self::Add => return concat(left, right).or_else(|| arithmetic(*self, left, right)),
+            Self::Equal => Canonical(left) == Canonical(right),
+            Self::NotEqual => Canonical(left) != Canonical(right),
+            Self::Less => ordering()?.is_lt(),
+            Self::LessOrEqual => ordering()?.is_le(),
+            Self::Greater => ordering()?.is_gt(),
+            Self::GreaterOrEqual => ordering()?.is_ge(),
+            _ => return arithmetic(*self, left, right),
+        };
+
+        Some(Value::Bool(result))
+    }
+}
+
+impl Function {
+    fn parse(name: &str) -> Result<Self> {
+        match name {
+            "len" => Ok(Self::Len),
+            "abs" => Ok(Self::Abs),
+            "lower" => Ok(Self::Lower),
+            "upper" => Ok(Self::Upper),
+            "substr" => Ok(Self::Substr),
+            "split" => Ok(Self::Split),
+            _ => Err("Unknown function, expected len, abs, lower, upper, substr, split or if"),
+        }
+    }
+
+    fn arity(&self) -> RangeInclusive<usize> {
+        match self {
+            Self::Len | Self::Abs | Self::Lower | Self::Upper => 1..=1,
+            Self::Split => 2..=2,
+            Self::Substr => 2..=3,
+        }
+    }
+
+    fn call(&self, args: &[Value]) -> Option<Value> {
+        match (self, args) {
+            (Self::Len, [Value::String(s)]) => Some(Value::from(s.chars().count())),
+            (Self::Len, [Value::Array(arr)]) => Some(Value::from(arr.len())),
+            (Self::Len, [Value::Object(obj)]) => Some(Value::from(obj.len())),
+            (Self::Abs, [Value::Number(n)]) => match n.as_i64() {
+                Some(i) if i != i64::MIN => Some(Value::from(i.abs())),
+                _ => n.as_f64().map(f64::abs).and_then(from_f64),
+            },
+            (Self::Lower, [Value::String(s)]) => Some(Value::from(s.to_lowercase())),
+            (Self::Upper, [Value::String(s)]) => Some(Value::from(s.to_uppercase())),
+            (Self::Substr, [Value::String(s), Value::Number(start), rest @ ..]) => {
+                let len = match rest {
+                    [] => None,
+                    [Value::Number(len)] => Some(len.as_i64()?),
+                    _ => return None,
+                };
+
+                Some(Value::from(substr(s, start.as_i64()?, len)))
+            }
+            (Self::Split, [Value::String(s), Value::String(separator)]) => {
+                let parts: Vec<Value> = match separator.as_str() {
+                    "" => s.chars().map(|c| Value::from(c.to_string())).collect(),
+                    separator => s.split(separator).map(Value::from).collect(),
+                };
+
+                Some(Value::Array(parts))
+            }
+            _ => None,
+        }
+    }
+}
+
+// Characters from `start`, counting from the end when negative, up to `len`
+// of them. Out of range positions are clamped.
+fn substr(s: &str, start: i64, len: Option<i64>) -> String {
+    let count = s.chars().count() as i64;
+    let start = match start {
+        start if start < 0 => (count + start).max(0),
+        start => start.min(count),
+    };
+    let len = len.unwrap_or(count).clamp(0, count - start);
+
+    s.chars().skip(start as usize).take(len as usize).collect()
+}
+
+// Joins strings, or a string with a number or boolean
+fn concat(left: &Value, right: &Value) -> Option<Value> {
+    let text = |value: &Value| match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(_) | Value::Bool(_) => Some(value.to_string()),
+        _ => None,
+    };
+
+    match (left, right) {
+        (Value::String(_), _) | (_, Value::String(_)) => {
+            Some(Value::from(text(left)? + &text(right)?))
+        }
+        _ => None,
+    }
+}
+
+// Integer arithmetic is exact unless it overflows, anything else is done on
+// floats. Operands other than numbers are undefined, as are other operators.
+fn arithmetic(op: BinaryOp, left: &Value, right: &Value) -> Option<Value> {
+    let (left, right) = match (left, right) {
+        (Value::Number(left), Value::Number(right)) => (left, right),
+        _ => return None,
+    };
+
+    if let (Some(l), Some(r)) = (left.as_i64(), right.as_i64()) {
+        let exact = match op {
+            BinaryOp::Add => l.checked_add(r),
+            BinaryOp::Subtract => l.checked_sub(r),
+            BinaryOp::Multiply => l.checked_mul(r),
+            BinaryOp::Remainder => l.checked_rem(r),
+            BinaryOp::Divide if l.checked_rem(r) == Some(0) => l.checked_div(r),
+            _ => None,
+        };
+
+        if let Some(n) = exact {
+            return Some(Value::from(n));
+        }
+    }
+
+    let (l, r) = (left.as_f64()?, right.as_f64()?);
+
+    from_f64(match op {
+        BinaryOp::Add => l + r,
+        BinaryOp::Subtract => l - r,
+        BinaryOp::Multiply => l * r,
+        BinaryOp::Divide => l / r,
+        BinaryOp::Remainder => l % r,
+        _ => return None,
+    })
+}
+
+// NaN and infinities aren't JSON numbers, so they are undefined.
+fn from_f64(n: f64) -> Option<Value> {
+    Number::from_f64(n).map(Value::Number)
+}
+
+// Splits on commas that aren't within parentheses or quotes.
+pub fn split_top_level(s: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut quote = None;
+    let mut start = 0;
+
+    for (i, c) in s.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'' | '`') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    parts.push(&s[start..]);
+    parts
+}
+
+struct Parser<'a> {
+    lexer: Lexer<'a>,
+}
+
+impl<'a> Parser<'a> {
+    // Pratt parser, only binary operators binding tighter than `min_power`
+    // are consumed.
+    fn expr(&mut self, min_power: u8) -> Result<Expr<'a>> {
+        let mut left = self.unary()?;
+
+        loop {
+            let op = match self.lexer.peek()? {
+                Some(Token::Symbol('+')) => BinaryOp::Add,
+                Some(Token::Symbol('-')) => BinaryOp::Subtract,
+                Some(Token::Symbol('*')) => BinaryOp::Multiply,
+                Some(Token::Symbol('/')) => BinaryOp::Divide,
+                Some(Token::Symbol('%')) => BinaryOp::Remainder,
+                Some(Token::Symbol('=') | Token::Operator("==")) => BinaryOp::Equal,
+                Some(Token::Operator("!=")) => BinaryOp::NotEqual,
+                Some(Token::Symbol('<')) => BinaryOp::Less,
+                Some(Token::Operator("<=")) => BinaryOp::LessOrEqual,
+                Some(Token::Symbol('>')) => BinaryOp::Greater,
+                Some(Token::Operator(">=")) => BinaryOp::GreaterOrEqual,
+                Some(Token::Ident(word)) if is_keyword(word, "and") => BinaryOp::And,
+                Some(Token::Ident(word)) if is_keyword(word, "or") => BinaryOp::Or,
+                _ => return Ok(left),
+            };
+
+            if op.binding_power() <= min_power {
+                return Ok(left);
+            }
+
+            self.lexer.next()?;
+            let right = self.expr(op.binding_power())?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr<'a>> {
+        match self.lexer.peek()? {
+            Some(Token::Symbol('-')) => {
+                self.lexer.next()?;
+                Ok(Expr::Negate(Box::new(self.unary()?)))
+            }
+            // Binds looser than comparisons: `not a = b` is `not (a = b)`
+            Some(Token::Ident(word)) if is_keyword(word, "not") => {
+                self.lexer.next()?;
+                Ok(Expr::Not(Box::new(self.expr(BinaryOp::And.binding_power())?)))
+            }
+            _ => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr<'a>> {
+        match self.lexer.next()? {
+            Some(Token::Number(n)) => Ok(Expr::Literal(Value::Number(n))),
+            Some(Token::String(s)) => Ok(Expr::Literal(Value::String(s))),
+            Some(Token::Symbol('(')) => {
+                let expr = self.expr(0)?;
+                self.expect(')')?;
+                Ok(expr)
+            }
+            Some(Token::Ident(name)) if self.lexer.peek()? == Some(Token::Symbol('(')) => {
+                self.call(name)
+            }
+            Some(Token::Ident(word)) if is_keyword(word, "case") => self.case(),
+            Some(Token::Ident("true")) => Ok(Expr::Literal(Value::Bool(true))),
+            Some(Token::Ident("false")) => Ok(Expr::Literal(Value::Bool(false))),
+            Some(Token::Ident("null")) => Ok(Expr::Literal(Value::Null)),
+            Some(Token::Ident(path)) | Some(Token::QuotedIdent(path)) => {
+                Ok(Expr::Path(FieldPath::parse(path)?))
+            }
+            _ => Err("Expected a field, value or `(` in expression"),
+        }
+    }
+
+    fn call(&mut self, name: &str) -> Result<Expr<'a>> {
+        if name == "if" {
+            return self.conditional();
+        }
+
+        let function = Function::parse(name)?;
+        self.expect('(')?;
+
+        let mut args = Vec::new();
+
+        if self.lexer.peek()? != Some(Token::Symbol(')')) {
+            loop {
+                args.push(self.expr(0)?);
+
+                if self.lexer.peek()? != Some(Token::Symbol(',')) {
+                    break;
+                }
+
+                self.lexer.next()?;
+            }
+        }
+
+        self.expect(')')?;
+
+        if !function.arity().contains(&args.len()) {
+            return Err("Wrong number of arguments in function call");
+        }
+
+        Ok(Expr::Call(function, args))
+    }
+
+    // if(condition, then, else)
+    fn conditional(&mut self) -> Result<Expr<'a>> {
+        self.expect('(')?;
+        let condition = self.expr(0)?;
+        self.expect(',')?;
+        let then = self.expr(0)?;
+        self.expect(',')?;
+        let otherwise = self.expr(0)?;
+        self.expect(')')?;
+
+        Ok(Expr::Case(vec![(condition, then)], Some(Box::new(otherwise))))
+    }
+
+    // case when condition then x [when ...] [else y] end
+    fn case(&mut self) -> Result<Expr<'a>> {
+        let mut branches = Vec::new();
+
+        while self.keyword("when")? {
+            let condition = self.expr(0)?;
+
+            if !self.keyword("then")? {
+                return Err("Expected `then` after `when` condition");
+            }
+
+            branches.push((condition, self.expr(0)?));
+        }
+
+        if branches.is_empty() {
+            return Err("Expected `when` after `case`");
+        }
+
+        let otherwise = match self.keyword("else")? {
+            true => Some(Box::new(self.expr(0)?)),
+            false => None,
+        };
+
+        if !self.keyword("end")? {
+            return Err("Expected `end` to close `case`");
+        }
+
+        Ok(Expr::Case(branches, otherwise))
+    }
+
+    // Consumes the keyword if it's next
+    fn keyword(&mut self, keyword: &str) -> Result<bool> {
+        match self.lexer.peek()? {
+            Some(Token::Ident(word)) if is_keyword(word, keyword) => {
+                self.lexer.next()?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<()> {
+        match self.lexer.next()? {
+            Some(Token::Symbol(c)) if c == symbol => Ok(()),
+            _ if symbol == ',' => Err("Expected `,` between function arguments"),
+            _ => Err("Unbalanced parentheses in expression"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    Number(Number),
+    String(String),
+    Ident(&'a str),
+    // `name with spaces`
+    QuotedIdent(&'a str),
+    // Two character comparisons: == != <= >=
+    Operator(&'a str),
+    // Any other punctuation, the parser decides what it means
+    Symbol(char),
+}
+
+#[derive(Clone, Copy)]
+struct Lexer<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn peek(&self) -> Result<Option<Token<'a>>> {
+        let mut lexer = *self;
+        lexer.next()
+    }
+
+    fn next(&mut self) -> Result<Option<Token<'a>>> {
+        let rest = &self.input[self.pos..];
+        let trimmed = rest.trim_start();
+        let start = self.pos + rest.len() - trimmed.len();
+
+        let c = match trimmed.chars().next() {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+
+        let (token, len) = if c.is_ascii_digit() {
+            number(trimmed)?
+        } else if is_ident_start(c) {
+            let len = ident_len(trimmed);
+            (Token::Ident(&trimmed[..len]), len)
+        } else if c == '`' {
+            let len = trimmed[1..]
+                .find('`')
+                .ok_or("Unterminated ` in expression")?;
+            (Token::QuotedIdent(&trimmed[1..len + 1]), len + 2)
+        } else if c == '"' || c == '\'' {
+            string(trimmed, c)?
+        } else if let Some(op) = ["==", "!=", "<=", ">="].iter().find(|op| trimmed.starts_with(*op)) {
+            (Token::Operator(op), 2)
+        } else {
+            (Token::Symbol(c), c.len_utf8())
+        };
+
+        // Whitespace before a token is only consumed along with it, so that
+        // `parse_prefix` stops right after the last token of the expression.
+        self.pos = start + len;
+        Ok(Some(token))
+    }
+}
+
+fn is_keyword(word: &str, keyword: &str) -> bool {
+    word.eq_ignore_ascii_case(keyword)
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '.' || c == '$' || c == '@'
+}
+
+fn is_ident_char(c: char) -> bool {
+    is_ident_start(c) || c.is_ascii_digit() || c == '[' || c == ']'
+}
+
+// A `-` continues an identifier when directly followed by another identifier
+// character, as in `x-request-id`.
+fn ident_len(s: &str) -> usize {
+    let mut chars = s.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let continues = match c {
+            '-' => chars.peek().is_some_and(|(_, next)| is_ident_char(*next)),
+            c => is_ident_char(c),
+        };
+
+        if !continues {
+            return i;
+        }
+    }
+
+    s.len()
+}
+
+// Digits with an optional fraction and exponent
+fn number(s: &str) -> Result<(Token<'_>, usize)> {
+    let bytes = s.as_bytes();
+    let digits = |from: usize| {
+        bytes[from..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count()
+    };
+
+    let mut len = digits(0);
+    let mut integer = true;
+
+    if bytes.get(len) == Some(&b'.') && bytes.get(len + 1).is_some_and(u8::is_ascii_digit) {
+        len += 1 + digits(len + 1);
+        integer = false;
+    }
+
+    if let Some(b'e' | b'E') = bytes.get(len) {
+        let sign = matches!(bytes.get(len + 1), Some(b'+' | b'-')) as usize;
+        let exponent = digits(len + 1 + sign);
+
+        if exponent > 0 {
+            len += 1 + sign + exponent;
+            integer = false;
+        }
+    }
+
+    let literal = &s[..len];
+
+    let number = match literal.parse::<i64>() {
+        Ok(n) if integer => Number::from(n),
+        _ => literal
+            .parse::<f64>()
+            .ok()
+            .and_then(Number::from_f64)
+            .ok_or("Invalid number in expression")?,
+    };
+
+    Ok((Token::Number(number), len))
+}
+
+// Quoted string, with backslash escaping the next character
+fn string(s: &str, quote: char) -> Result<(Token<'_>, usize)> {
+    let mut result = String::new();
+    let mut chars = s.char_indices().skip(1);
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some((_, escaped)) => result.push(escaped),
+                None => break,
+            },
+            c if c == quote => return Ok((Token::String(result), i + c.len_utf8())),
+            c => result.push(c),
+        }
+    }
+
+    Err("Unterminated string in expression")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn eval(expr: &str, row: Value) -> Option<Value> {
+        Expr::parse(expr).unwrap().eval(&row)
+    }
+
+    #[test]
+    fn arithmetic_precedence() {
+        assert_eq!(eval("1 + 2 * 3", json!({})), Some(json!(7)));
+        assert_eq!(eval("(1 + 2) * 3", json!({})), Some(json!(9)));
+        assert_eq!(eval("10 - 4 - 3", json!({})), Some(json!(3)));
+        assert_eq!(eval("-2 * 3 + 7 % 4", json!({})), Some(json!(-3)));
+        assert_eq!(eval("7 / 2", json!({})), Some(json!(3.5)));
+        assert_eq!(eval("6 / 2", json!({})), Some(json!(3)));
+    }
+
+    #[test]
+    fn paths() {
+        let row = json!({"a-b": 5, "address": {"zip": 2}, "powers": [1, 4], "x y": 1});
+
+        assert_eq!(eval("a-b", row.clone()), Some(json!(5)));
+        assert_eq!(eval("address.zip * 10", row.clone()), Some(json!(20)));
+        assert_eq!(eval("powers.[].1", row.clone()), Some(json!(4)));
+        assert_eq!(eval("`x y` + 1", row.clone()), Some(json!(2)));
+        assert_eq!(eval("len(powers)", row.clone()), Some(json!(2)));
+        assert_eq!(eval("missing + 1", row), None);
+    }
+
+    #[test]
+    fn undefined_arithmetic() {
+        assert_eq!(eval("1 / 0", json!({})), None);
+        assert_eq!(eval("1 % 0", json!({})), None);
+        assert_eq!(eval("a * 2", json!({"a": "x"})), None);
+        assert_eq!(eval("abs(a)", json!({"a": i64::MIN})), Some(json!(9.223372036854776e18)));
+        assert_eq!(eval("a / -1", json!({"a": i64::MIN})), Some(json!(9.223372036854776e18)));
+        assert_eq!(eval("a + 1", json!({"a": i64::MAX})), Some(json!(9.223372036854776e18)));
+    }
+
+    #[test]
+    fn comparisons() {
+        let row = json!({"a": 2, "b": 2.0, "s": "x"});
+
+        assert_eq!(eval("a = b", row.clone()), Some(json!(true)));
+        assert_eq!(eval("a == 2 and s != \"y\"", row.clone()), Some(json!(true)));
+        assert_eq!(eval("a > 1 + 2 or s < \"y\"", row.clone()), Some(json!(true)));
+        assert_eq!(eval("not a >= 3", row.clone()), Some(json!(true)));
+        assert_eq!(eval("a < s", row.clone()), None);
+        assert_eq!(eval("missing = 1 or a <= 2", row), Some(json!(true)));
+    }
+
+    #[test]
+    fn conditionals() {
+        let size = "case when n < 10 then \"small\" \
+            when n < 100 then \"medium\" \
+            else \"large\" end";
+
+        assert_eq!(eval(size, json!({"n": 5})), Some(json!("small")));
+        assert_eq!(eval(size, json!({"n": 50})), Some(json!("medium")));
+        assert_eq!(eval(size, json!({"n": 500})), Some(json!("large")));
+        assert_eq!(eval(size, json!({})), Some(json!("large")));
+        assert_eq!(eval("case when n = 1 then 2 end", json!({"n": 0})), None);
+        assert_eq!(eval("if(ok, 1, 2)", json!({"ok": true})), Some(json!(1)));
+        assert_eq!(eval("if(ok, 1, 2)", json!({"ok": "yes"})), Some(json!(2)));
+    }
+
+    #[test]
+    fn string_functions() {
+        let row = json!({"name": "Ünïcode", "path": "a/b/c"});
+
+        assert_eq!(eval("lower(name)", row.clone()), Some(json!("ünïcode")));
+        assert_eq!(eval("upper(name)", row.clone()), Some(json!("ÜNÏCODE")));
+        assert_eq!(eval("substr(name, 1, 3)", row.clone()), Some(json!("nïc")));
+        assert_eq!(eval("substr(name, -4)", row.clone()), Some(json!("code")));
+        assert_eq!(eval("substr(name, 5, 100)", row.clone()), Some(json!("de")));
+        assert_eq!(eval("substr(name, 100)", row.clone()), Some(json!("")));
+        assert_eq!(eval("split(path, \"/\")", row.clone()), Some(json!(["a", "b", "c"])));
+        assert_eq!(eval("split(\"ab\", \"\")", row.clone()), Some(json!(["a", "b"])));
+        assert_eq!(eval("\"n=\" + 1", row.clone()), Some(json!("n=1")));
+        assert_eq!(eval("lower(1)", row), None);
+    }
+
+    #[test]
+    fn invalid_conditionals() {
+        for expr in ["case end", "case when a then 1", "case when a 1 end", "if(a, 1)"] {
+            assert!(Expr::parse(expr).is_err(), "{}", expr);
+        }
+    }
+
+    #[test]
+    fn prefix_stops_before_comparisons() {
+        let (expr, consumed) = Expr::parse_prefix("len(tags) + 1 >= 3").unwrap();
+
+        assert_eq!(consumed, 13);
+        assert_eq!(expr.eval(&json!({"tags": [1, 2]})), Some(json!(3)));
+    }
+
+    #[test]
+    fn invalid_expressions() {
+        for expr in ["1 +", "(1", "len(a", "nope(a)", "len(a, b)", "`a", "1 2"] {
+            assert!(Expr::parse(expr).is_err(), "{}", expr);
+        }
+    }
+
+    #[test]
+    fn splits_on_top_level_commas() {
+        assert_eq!(
+            split_top_level("a,substr(b, 0, 2),\"c,d\", e"),
+            ["a", "substr(b, 0, 2)", "\"c,d\"", " e"]
+        );
+    }
+}
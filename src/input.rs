@@ -0,0 +1,54 @@
+use std::fs;
+
+use serde_json::Value;
+
+use crate::{exit_with_error, Result};
+
+mod csv;
+
+pub(crate) use csv::infer;
+
+// Rows read from a file, in the format given by its extension:
+// `.jsonl` for JSON lines, `.csv` for CSV with a header row, JSON otherwise.
+pub struct Input {
+    pub values: Vec<Value>,
+    // The file holds a single JSON object rather than rows
+    pub single: bool,
+}
+
+pub fn read(path: &str) -> Result<Input> {
+    let data = match fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(e) => exit_with_error(&format!("Could not read {}: {}", path, e)),
+    };
+
+    if path.ends_with(".jsonl") {
+        Ok(rows(data.lines().map(deserialize).collect()))
+    } else if path.ends_with(".csv") {
+        Ok(rows(csv::parse(&data)?))
+    } else {
+        match deserialize(&data) {
+            Value::Array(values) => Ok(rows(values)),
+            value @ Value::Object(_) => Ok(Input {
+                values: vec![value],
+                single: true,
+            }),
+            _ => Err("Unexpected path"),
+        }
+    }
+}
+
+fn rows(values: Vec<Value>) -> Input {
+    Input {
+        values,
+        single: false,
+    }
+}
+
+// Handle error better way, that matches Clap style
+fn deserialize(data: &str) -> Value {
+    match serde_json::from_str(data) {
+        Ok(value) => value,
+        Err(e) => exit_with_error(&format!("Invalid JSON {}", e)),
+    }
+}
@@ -0,0 +1,190 @@
+use serde_json::{Map, Number, Value};
+
+use crate::Result;
+
+// Parses RFC 4180 CSV into one object per record, keyed by the header row.
+// Fields can be quoted to hold commas, newlines and `""` escaped quotes.
+// Values are typed: empty fields are null, then booleans and numbers are
+// recognized, and anything else is a string.
+pub fn parse(data: &str) -> Result<Vec<Value>> {
+    let mut records = Records {
+        input: data.strip_prefix('\u{feff}').unwrap_or(data),
+    };
+
+    let Some(headers) = records.next().transpose()? else {
+        return Ok(Vec::new());
+    };
+
+    let mut values = Vec::new();
+
+    for record in records {
+        let record = record?;
+
+        // Blank lines are skipped
+        if record.len() == 1 && record[0].is_empty() {
+            continue;
+        }
+
+        if record.len() > headers.len() {
+            return Err("CSV record has more fields than the header");
+        }
+
+        let row: Map<String, Value> = headers
+            .iter()
+            .zip(record)
+            .map(|(header, field)| (header.clone(), infer(field)))
+            .collect();
+
+        values.push(Value::Object(row));
+    }
+
+    Ok(values)
+}
+
+// Also types the captures of --extract
+pub(crate) fn infer(field: String) -> Value {
+    match field.as_str() {
+        "" => Value::Null,
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        // Numbers too large for a float, like 1e400, stay strings
+        s if is_number(s) => match s.parse::<i64>() {
+            Ok(i) => Value::from(i),
+            Err(_) => match s.parse().ok().and_then(Number::from_f64) {
+                Some(n) => Value::Number(n),
+                None => Value::String(field),
+            },
+        },
+        _ => Value::String(field),
+    }
+}
+
+// Digits with an optional sign, fraction and exponent. Numbers with leading
+// zeros, like zip codes or ids, are kept as strings.
+fn is_number(s: &str) -> bool {
+    let digits = s.strip_prefix(['-', '+']).unwrap_or(s);
+    let (mantissa, exponent) = match digits.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (digits, None),
+    };
+    let (integer, fraction) = match mantissa.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (mantissa, None),
+    };
+
+    let all_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+
+    all_digits(integer)
+        && !(integer.len() > 1 && integer.starts_with('0'))
+        && fraction.is_none_or(all_digits)
+        && exponent.is_none_or(|e| all_digits(e.strip_prefix(['-', '+']).unwrap_or(e)))
+}
+
+// Iterates over records, each a list of fields.
+struct Records<'a> {
+    input: &'a str,
+}
+
+impl<'a> Iterator for Records<'a> {
+    type Item = Result<Vec<String>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.input.is_empty() {
+            return None;
+        }
+
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut chars = self.input.char_indices().peekable();
+        let mut quoted = false;
+        let mut end = self.input.len();
+
+        while let Some((i, c)) = chars.next() {
+            match (quoted, c) {
+                (true, '"') if chars.peek().is_some_and(|(_, c)| *c == '"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                (true, '"') => quoted = false,
+                (true, c) => field.push(c),
+                (false, '"') if field.is_empty() => quoted = true,
+                (false, ',') => fields.push(std::mem::take(&mut field)),
+                (false, '\n') => {
+                    end = i + 1;
+                    break;
+                }
+                (false, '\r') if chars.peek().is_some_and(|(_, c)| *c == '\n') => {}
+                (false, c) => field.push(c),
+            }
+        }
+
+        self.input = &self.input[end..];
+
+        if quoted {
+            return Some(Err("Unterminated quoted field in CSV"));
+        }
+
+        fields.push(field);
+        Some(Ok(fields))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn records_keyed_by_header() {
+        assert_eq!(
+            parse("id,name\n1,Ann\n2,Bob\n").unwrap(),
+            vec![json!({"id": 1, "name": "Ann"}), json!({"id": 2, "name": "Bob"})]
+        );
+    }
+
+    #[test]
+    fn quoted_fields() {
+        let data = "a,b\r\n\"x, \"\"y\"\"\",\"line\nbreak\"\r\n";
+
+        assert_eq!(
+            parse(data).unwrap(),
+            vec![json!({"a": "x, \"y\"", "b": "line\nbreak"})]
+        );
+    }
+
+    #[test]
+    fn blank_lines_bom_and_short_records() {
+        assert_eq!(
+            parse("\u{feff}a,b\n\n1\n").unwrap(),
+            vec![json!({"a": 1})]
+        );
+        assert_eq!(parse("").unwrap(), Vec::<Value>::new());
+        assert_eq!(parse("a,b").unwrap(), Vec::<Value>::new());
+    }
+
+    #[test]
+    fn invalid_records() {
+        assert!(parse("a\n1,2\n").is_err());
+        assert!(parse("a\n\"open\n").is_err());
+    }
+
+    #[test]
+    fn typed_fields() {
+        let infer = |s: &str| infer(String::from(s));
+
+        assert_eq!(infer(""), Value::Null);
+        assert_eq!(infer("true"), json!(true));
+        assert_eq!(infer("False"), json!("False"));
+        assert_eq!(infer("-12"), json!(-12));
+        assert_eq!(infer("+1.5e3"), json!(1500.0));
+        assert_eq!(infer("0.25"), json!(0.25));
+        assert_eq!(infer("18446744073709551616"), json!(18446744073709551616.0));
+        assert_eq!(infer("1e400"), json!("1e400"));
+        assert_eq!(infer("007"), json!("007"));
+        assert_eq!(infer("1."), json!("1."));
+        assert_eq!(infer(".5"), json!(".5"));
+        assert_eq!(infer("1e"), json!("1e"));
+        assert_eq!(infer("NaN"), json!("NaN"));
+    }
+}
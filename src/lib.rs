@@ -1,67 +1,59 @@
-use std::{cmp, collections::HashMap, fs, process};
+use std::{cmp, collections::HashMap, path::Path, process};
 
 use serde_json::Value;
 
 pub mod action;
 pub mod args;
+pub mod diff;
+pub mod expr;
+pub mod input;
 pub mod path;
+pub mod schema;
 pub mod table;
+pub mod time;
 
-use action::{Action, Filter, Flatten, Select, Sort};
+use action::{
+    Action, Bucket, CountBy, Describe, Distinct, Extract, Filter, Flatten, Grep, Group, Join, Limit,
+    Mask, Pivot, Sample, Select, Sort, Split, Unpivot, Window, With,
+};
+use args::SchemaFormat;
+use diff::Diff;
+use schema::Schema;
 use table::{Cell, DrawOptions, Header};
 
 pub type Result<T> = std::result::Result<T, &'static str>;
 
 pub fn run(args: args::Args) {
-    let data = fs::read_to_string(&args.file).unwrap();
+    // Compiled once, to filter rows and to highlight matches
+    let result = Grep::new(&args.grep).and_then(|grep| {
+        let input = input::read(&args.file)?;
 
-    let result = if args.file.ends_with(".jsonl") {
-        render_json_lines(&data, &args)
-    } else {
-        render_json(&data, &args)
-    };
+        match &args.diff {
+            Some(old) => render_diff(old, input.values, &args, &grep),
+            None => render_table(input.values, &args, input.single, &grep),
+        }
+    });
 
     if let Err(err) = result {
         exit_with_error(err);
     }
 }
 
-fn render_json(data: &str, args: &args::Args) -> Result<()> {
-    let value = deserialize(data);
+fn render_table(values: Vec<Value>, args: &args::Args, flip: bool, grep: &Grep) -> Result<()> {
+    let values = apply(values, &actions(args, grep)?);
 
-    match value {
-        Value::Array(values) => Ok(render_table(values, &args, false)?),
-        Value::Object(_) => Ok(render_table(vec![value], &args, true)?),
-        _ => Err("Unexpected path"),
-    }
-}
+    if let Some(format) = args.schema {
+        let schema = Schema::infer(&values);
 
-fn render_json_lines(data: &str, args: &args::Args) -> Result<()> {
-    let values: Vec<Value> = data.lines().map(|line| deserialize(line)).collect();
-
-    Ok(render_table(values, &args, false)?)
-}
-
-// Handle error better way, that matches Clap style
-fn deserialize(data: &str) -> Value {
-    match serde_json::from_str(&data) {
-        Ok(value) => value,
-        Err(e) => exit_with_error(&format!("Invalid JSON {}", e)),
-    }
-}
-
-fn render_table(mut values: Vec<Value>, args: &args::Args, flip: bool) -> Result<()> {
-    let actions: Vec<Box<dyn Action>> = vec![
-        Box::new(Filter::parse(&args.filter)?),
-        Box::new(Select::new(&args.select, args.select_mode)?),
-        Box::new(Flatten {
-            fields: &args.flatten,
-        }),
-        Box::new(Sort { field: &args.sort }),
-    ];
+        match format {
+            SchemaFormat::Tree => print!("{}", schema.tree()),
+            SchemaFormat::JsonSchema => println!(
+                "{}",
+                serde_json::to_string_pretty(&schema.json_schema()).unwrap()
+            ),
+        }
 
-    for action in actions.iter() {
-        values = action.apply(values);
+        return Ok(());
     }
 
     if values.is_empty() {
@@ -78,6 +70,8 @@ fn render_table(mut values: Vec<Value>, args: &args::Args, flip: bool) -> Result
     let draw_options = DrawOptions {
         color: args.color,
         flip,
+        vertical: args.vertical,
+        highlight: grep.pattern.clone(),
     };
 
     println!("{}", table::draw_table(&headers, &rows, draw_options));
@@ -85,7 +79,152 @@ fn render_table(mut values: Vec<Value>, args: &args::Args, flip: bool) -> Result
     Ok(())
 }
 
-fn get_headers(values: &Vec<Value>) -> Vec<Header> {
+// Both files go through the same actions before being compared
+fn render_diff(old: &str, new: Vec<Value>, args: &args::Args, grep: &Grep) -> Result<()> {
+    let actions = actions(args, grep)?;
+    let old = apply(input::read(old)?.values, &actions);
+    let new = apply(new, &actions);
+
+    let (headers, rows) = Diff::parse(&args.key, args.all)?.table(&old, &new);
+
+    if rows.is_empty() {
+        return Ok(());
+    }
+
+    let draw_options = DrawOptions {
+        color: args.color,
+        flip: false,
+        vertical: args.vertical,
+        highlight: grep.pattern.clone(),
+    };
+
+    println!("{}", table::draw_table(&headers, &rows, draw_options));
+
+    Ok(())
+}
+
+fn actions<'a>(args: &'a args::Args, grep: &Grep) -> Result<Vec<Box<dyn Action + 'a>>> {
+    let limit = Limit {
+        offset: args.offset,
+        head: args.head,
+        tail: args.tail,
+    };
+
+    let window = Window::parse(&args.window, args.nulls)?;
+
+    // Windows need all rows, not only the top ones
+    let mut sort = Sort::parse(&args.sort, args.nulls)?;
+    sort.top = limit.top().filter(|_| window.is_none());
+
+    let mut actions: Vec<Box<dyn Action>> = Vec::new();
+
+    if let Some([file, on, keys]) = args.join.as_deref() {
+        if on != "on" {
+            return Err("Expected `--join FILE on KEYS`, like `--join users.csv on user_id=id`");
+        }
+
+        let prefix = match &args.join_prefix {
+            Some(prefix) => prefix.clone(),
+            None => {
+                let stem = Path::new(file).file_stem().unwrap_or_default();
+                format!("{}.", stem.to_string_lossy())
+            }
+        };
+
+        let right = input::read(file)?.values;
+        actions.push(Box::new(Join::new(right, keys, args.join_kind, prefix)?));
+    }
+
+    actions.push(Box::new(Extract::parse(&args.extract)?));
+    actions.push(Box::new(Split::parse(&args.split)?));
+    actions.push(Box::new(With::parse(&args.with)?));
+    actions.push(Box::new(grep.clone()));
+    actions.push(Box::new(Filter::parse(&args.filter, &args.or_filter)?));
+
+    if let Some(sample) = Sample::parse(&args.sample, args.seed)? {
+        actions.push(Box::new(sample));
+    }
+
+    actions.push(Box::new(Select::new(&args.select, args.select_mode)?));
+    actions.push(Box::new(Flatten {
+        fields: &args.flatten,
+    }));
+
+    // Empty buckets are filled once rows are counted or grouped by bucket only
+    let mut fill = None;
+
+    if let Some(bucket) = Bucket::parse(&args.bucket)? {
+        let by = args.count_by.as_deref().or(args.group_by.as_deref());
+
+        if by.map(str::trim) == Some(bucket.name) {
+            fill = Some(bucket.fill());
+        }
+
+        actions.push(Box::new(bucket));
+    }
+
+    let distinct = Distinct::parse(
+        args.distinct,
+        &args.distinct_on,
+        args.keep,
+        args.distinct_count,
+    )?;
+
+    if let Some(distinct) = distinct {
+        actions.push(Box::new(distinct));
+    }
+
+    if let Some(count_by) = CountBy::parse(&args.count_by, args.top, args.bar)? {
+        actions.push(Box::new(count_by));
+    }
+
+    if let Some(group) = Group::parse(&args.group_by, &args.agg)? {
+        actions.push(Box::new(group));
+    }
+
+    if let Some(fill) = fill {
+        actions.push(Box::new(fill));
+    }
+
+    if let Some(pivot) = Pivot::parse(&args.pivot)? {
+        actions.push(Box::new(pivot));
+    }
+
+    if let Some(unpivot) = Unpivot::parse(&args.unpivot)? {
+        actions.push(Box::new(unpivot));
+    }
+
+    actions.push(Box::new(sort));
+
+    if let Some(window) = window {
+        actions.push(Box::new(window));
+    }
+
+    actions.push(Box::new(limit));
+
+    // Last, so that derived values are masked too
+    let mask = Mask::parse(&args.mask, args.mask_auto, args.mask_mode, &args.mask_key)?;
+
+    if let Some(mask) = mask {
+        actions.push(Box::new(mask));
+    }
+
+    if args.describe {
+        actions.push(Box::new(Describe));
+    }
+
+    Ok(actions)
+}
+
+fn apply(mut values: Vec<Value>, actions: &[Box<dyn Action + '_>]) -> Vec<Value> {
+    for action in actions.iter() {
+        values = action.apply(values);
+    }
+
+    values
+}
+
+fn get_headers(values: &[Value]) -> Vec<Header> {
     let mut seen: HashMap<&str, usize> = HashMap::new();
     let mut headers: Vec<String> = Vec::new();
 
@@ -97,7 +236,7 @@ fn get_headers(values: &Vec<Value>) -> Vec<Header> {
                         seen.insert(key, cmp::max(*max_width, len(value)));
                     }
                     None => {
-                        seen.insert(key, cmp::max(key.len(), len(value)));
+                        seen.insert(key, cmp::max(key.chars().count(), len(value)));
                         headers.push(String::from(key));
                     }
                 }
@@ -120,20 +259,20 @@ fn len(value: &Value) -> usize {
         Value::Null => 4,    // null
         Value::Bool(_) => 5, // true or false
         Value::Number(n) => n.to_string().len(),
-        Value::String(s) => s.len() + 2,
+        Value::String(s) => s.chars().count() + 2,
         Value::Array(_) => 4,  // [..]
         Value::Object(_) => 4, // {..}
     }
 }
 
-fn value_to_vec(value: &Value, headers: &Vec<Header>) -> Vec<Cell> {
+fn value_to_vec(value: &Value, headers: &[Header]) -> Vec<Cell> {
     match value {
         Value::Object(object) => headers
             .iter()
             .map(|header| {
                 object
                     .get(&header.name)
-                    .map(|val| to_cell(val))
+                    .map(to_cell)
                     .unwrap_or(Cell::string(String::from("")))
             })
             .collect(),
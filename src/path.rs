@@ -1,4 +1,6 @@
-const ARRAY_SELECTOR: &'static str = "[]";
+const ARRAY_SELECTOR: &str = "[]";
+
+use serde_json::Value;
 
 use crate::Result;
 
@@ -12,7 +14,7 @@ pub struct FieldPath<'a> {
 
 impl<'a> FieldPath<'a> {
     pub fn parse(path: &'a str) -> Result<Self> {
-        let mut buff = path.split('.').filter(|p| !p.is_empty()).into_iter();
+        let mut buff = path.split('.').filter(|p| !p.is_empty());
 
         let mut selectors = Vec::new();
 
@@ -36,6 +38,37 @@ impl<'a> FieldPath<'a> {
             path_str: path,
         })
     }
+
+    // Resolves the path within `value`. A key matching the whole path, like the
+    // ones created by flatten or select, takes precedence over walking the path.
+    pub fn get<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        if let Some(value) = value.get(self.path_str) {
+            return Some(value);
+        }
+
+        self.selectors
+            .iter()
+            .try_fold(value, |value, selector| match (selector, value) {
+                (Selector::Field(field), Value::Object(obj)) => obj.get(*field),
+                (Selector::IntoArray(index), Value::Array(arr)) => arr.get(*index),
+                _ => None,
+            })
+    }
+
+    // Like `get`, for changing the value in place
+    pub fn get_mut<'v>(&self, value: &'v mut Value) -> Option<&'v mut Value> {
+        if value.get(self.path_str).is_some() {
+            return value.get_mut(self.path_str);
+        }
+
+        self.selectors
+            .iter()
+            .try_fold(value, |value, selector| match (selector, value) {
+                (Selector::Field(field), Value::Object(obj)) => obj.get_mut(*field),
+                (Selector::IntoArray(index), Value::Array(arr)) => arr.get_mut(*index),
+                _ => None,
+            })
+    }
 }
 
 #[derive(Debug)]
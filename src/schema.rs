@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+
+use serde_json::{json, Map, Value};
+
+const JSON_SCHEMA_DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+// Indexed by `kind`
+const TYPES: [&str; 6] = ["null", "bool", "number", "string", "array", "object"];
+const NULL: usize = 0;
+const NUMBER: usize = 2;
+const ARRAY: usize = 4;
+const OBJECT: usize = 5;
+
+// Union of the shapes of all values seen at one path. Fields keep the order
+// in which they are first seen, and array elements share a single schema.
+#[derive(Default)]
+pub struct Schema {
+    // Values seen, by type
+    counts: [usize; 6],
+    integers: usize,
+    fields: Vec<(String, Schema)>,
+    index: HashMap<String, usize>,
+    items: Option<Box<Schema>>,
+}
+
+impl Schema {
+    pub fn infer(values: &[Value]) -> Self {
+        let mut schema = Self::default();
+
+        for value in values {
+            schema.add(value);
+        }
+
+        schema
+    }
+
+    fn add(&mut self, value: &Value) {
+        self.counts[kind(value)] += 1;
+
+        match value {
+            Value::Number(n) if n.is_i64() || n.is_u64() => self.integers += 1,
+            Value::Array(items) => {
+                let schema = self.items.get_or_insert_with(Default::default);
+
+                for item in items {
+                    schema.add(item);
+                }
+            }
+            Value::Object(map) => {
+                for (key, value) in map {
+                    let i = match self.index.get(key) {
+                        Some(i) => *i,
+                        None => {
+                            self.fields.push((key.clone(), Self::default()));
+                            self.index.insert(key.clone(), self.fields.len() - 1);
+                            self.fields.len() - 1
+                        }
+                    };
+
+                    self.fields[i].1.add(value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn seen(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    // One line per path, with its types, presence and nullability:
+    //   .                       object         3 rows
+    //   ├── powers              array<string>  3/3 (100%)
+    //   │   └── powers.[]       string         11 items
+    //   └── address             object         2/3 (67%)  nullable
+    //       └── address.planet  string         2/2 (100%)
+    // `[]` stands for any element of an array.
+    pub fn tree(&self) -> String {
+        let mut lines = vec![(
+            String::from("."),
+            self.types(),
+            format!("{} rows", self.seen()),
+        )];
+
+        self.children("", "", &mut lines);
+
+        let path_width = lines
+            .iter()
+            .map(|(path, ..)| path.chars().count())
+            .max()
+            .unwrap_or(0);
+        let types_width = lines
+            .iter()
+            .map(|(_, types, _)| types.len())
+            .max()
+            .unwrap_or(0);
+
+        lines
+            .into_iter()
+            .map(|(path, types, presence)| {
+                let padding = path_width - path.chars().count();
+                let line = format!(
+                    "{}{}  {:types_width$}  {}",
+                    path,
+                    " ".repeat(padding),
+                    types,
+                    presence
+                );
+                format!("{}\n", line.trim_end())
+            })
+            .collect()
+    }
+
+    fn children(&self, path: &str, prefix: &str, lines: &mut Vec<(String, String, String)>) {
+        let mut children: Vec<(String, &Self, String)> = self
+            .fields
+            .iter()
+            .map(|(name, field)| {
+                let presence = format!(
+                    "{}/{} ({}%)",
+                    field.seen(),
+                    self.counts[OBJECT],
+                    percent(field.seen(), self.counts[OBJECT])
+                );
+
+                (join(path, name), field, presence)
+            })
+            .collect();
+
+        if let Some(items) = self.items.as_ref().filter(|items| items.seen() > 0) {
+            children.push((join(path, "[]"), items, format!("{} items", items.seen())));
+        }
+
+        let last = children.len().saturating_sub(1);
+
+        for (i, (path, schema, mut presence)) in children.into_iter().enumerate() {
+            let (branch, indent) = match i == last {
+                true => ("└── ", "    "),
+                false => ("├── ", "│   "),
+            };
+
+            if schema.counts[NULL] > 0 {
+                presence.push_str("  nullable");
+            }
+
+            lines.push((format!("{}{}{}", prefix, branch, path), schema.types(), presence));
+            schema.children(&path, &format!("{}{}", prefix, indent), lines);
+        }
+    }
+
+    // Observed types other than null, like `number | string` or `array<string>`
+    fn types(&self) -> String {
+        let types: Vec<String> = (0..TYPES.len())
+            .filter(|&kind| kind != NULL && self.counts[kind] > 0)
+            .map(|kind| match (kind, &self.items) {
+                (ARRAY, Some(items)) if items.seen() > 0 => format!("array<{}>", items.all_types()),
+                _ => String::from(TYPES[kind]),
+            })
+            .collect();
+
+        match types.is_empty() {
+            true if self.counts[NULL] > 0 => String::from("null"),
+            _ => types.join(" | "),
+        }
+    }
+
+    // Element types of an array, nulls included
+    fn all_types(&self) -> String {
+        match (self.counts[NULL] > 0, self.types().as_str()) {
+            (true, "null") | (false, _) => self.types(),
+            (true, types) => format!("{} | null", types),
+        }
+    }
+
+    pub fn json_schema(&self) -> Value {
+        let mut schema = Map::new();
+        schema.insert(String::from("$schema"), json!(JSON_SCHEMA_DRAFT));
+        schema.extend(self.json_schema_keywords());
+
+        Value::Object(schema)
+    }
+
+    fn json_schema_keywords(&self) -> Map<String, Value> {
+        let mut schema = Map::new();
+
+        let types: Vec<&str> = (0..TYPES.len())
+            .filter(|&kind| self.counts[kind] > 0)
+            .map(|kind| match TYPES[kind] {
+                "bool" => "boolean",
+                "number" if self.integers == self.counts[NUMBER] => "integer",
+                name => name,
+            })
+            .collect();
+
+        match types.as_slice() {
+            [] => {}
+            [name] => {
+                schema.insert(String::from("type"), json!(name));
+            }
+            _ => {
+                schema.insert(String::from("type"), json!(types));
+            }
+        }
+
+        if self.counts[OBJECT] > 0 {
+            let properties: Map<String, Value> = self
+                .fields
+                .iter()
+                .map(|(name, field)| (name.clone(), Value::Object(field.json_schema_keywords())))
+                .collect();
+
+            // Fields present in every object
+            let required: Vec<&str> = self
+                .fields
+                .iter()
+                .filter(|(_, field)| field.seen() == self.counts[OBJECT])
+                .map(|(name, _)| name.as_str())
+                .collect();
+
+            schema.insert(String::from("properties"), Value::Object(properties));
+
+            if !required.is_empty() {
+                schema.insert(String::from("required"), json!(required));
+            }
+        }
+
+        if let Some(items) = self.items.as_ref().filter(|items| items.seen() > 0) {
+            schema.insert(String::from("items"), Value::Object(items.json_schema_keywords()));
+        }
+
+        schema
+    }
+}
+
+fn kind(value: &Value) -> usize {
+    match value {
+        Value::Null => 0,
+        Value::Bool(_) => 1,
+        Value::Number(_) => 2,
+        Value::String(_) => 3,
+        Value::Array(_) => 4,
+        Value::Object(_) => 5,
+    }
+}
+
+// Child paths use the `path::FieldPath` syntax
+fn join(path: &str, name: &str) -> String {
+    match path {
+        "" => String::from(name),
+        _ => format!("{}.{}", path, name),
+    }
+}
+
+fn percent(count: usize, total: usize) -> usize {
+    match total {
+        0 => 0,
+        _ => (count * 100 + total / 2) / total,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows() -> Vec<Value> {
+        vec![
+            json!({"name": "a", "powers": ["x", "y"], "address": {"planet": "earth"}}),
+            json!({"name": "b", "powers": [], "address": null}),
+            json!({"name": 1, "powers": ["z"]}),
+        ]
+    }
+
+    #[test]
+    fn tree() {
+        assert_eq!(
+            Schema::infer(&rows()).tree(),
+            "\
+.                       object           3 rows
+├── name                number | string  3/3 (100%)
+├── powers              array<string>    3/3 (100%)
+│   └── powers.[]       string           3 items
+└── address             object           2/3 (67%)  nullable
+    └── address.planet  string           1/1 (100%)
+"
+        );
+    }
+
+    #[test]
+    fn json_schema() {
+        assert_eq!(
+            Schema::infer(&rows()).json_schema(),
+            json!({
+                "$schema": JSON_SCHEMA_DRAFT,
+                "type": "object",
+                "properties": {
+                    "name": {"type": ["integer", "string"]},
+                    "powers": {"type": "array", "items": {"type": "string"}},
+                    "address": {
+                        "type": ["null", "object"],
+                        "properties": {"planet": {"type": "string"}},
+                        "required": ["planet"],
+                    },
+                },
+                "required": ["name", "powers"],
+            })
+        );
+    }
+
+    #[test]
+    fn integers_and_floats() {
+        let schema = Schema::infer(&[json!(1), json!(2.5)]);
+        assert_eq!(schema.json_schema()["type"], "number");
+    }
+
+    #[test]
+    fn nullable_array_items() {
+        let schema = Schema::infer(&[json!([1, null]), json!([null])]);
+        assert_eq!(schema.types(), "array<number | null>");
+        assert_eq!(Schema::infer(&[json!([null])]).types(), "array<null>");
+    }
+
+    #[test]
+    fn percents_round() {
+        assert_eq!(percent(2, 3), 67);
+        assert_eq!(percent(1, 3), 33);
+        assert_eq!(percent(0, 0), 0);
+    }
+}
@@ -1,3 +1,5 @@
+use regex::Regex;
+
 use crate::args::Color;
 
 mod draw;
@@ -8,6 +10,10 @@ pub use draw::draw_table;
 pub struct DrawOptions {
     pub color: Color,
     pub flip: bool,
+    // Rows are drawn as blocks of `field│value` lines, with a record header each
+    pub vertical: bool,
+    // Matches are highlighted within cells, when color is enabled
+    pub highlight: Option<Regex>,
 }
 
 #[derive(Debug)]
@@ -20,6 +26,8 @@ pub struct Header {
 pub struct Cell {
     pub content: String,
     pub cell_type: CellType,
+    // Shown before the content as `previous → content`, for changed cells in a diff
+    pub previous: Option<Box<Cell>>,
 }
 
 #[derive(Debug)]
@@ -29,6 +37,10 @@ pub enum CellType {
     Number,
     String,
     Collapsed,
+    // Markers of rows in a diff
+    Added,
+    Removed,
+    Changed,
 }
 
 impl Cell {
@@ -36,6 +48,7 @@ impl Cell {
         Self {
             content,
             cell_type: CellType::Null,
+            previous: None,
         }
     }
 
@@ -43,6 +56,7 @@ impl Cell {
         Self {
             content,
             cell_type: CellType::Bool,
+            previous: None,
         }
     }
 
@@ -50,6 +64,7 @@ impl Cell {
         Self {
             content,
             cell_type: CellType::Number,
+            previous: None,
         }
     }
 
@@ -57,6 +72,7 @@ impl Cell {
         Self {
             content,
             cell_type: CellType::String,
+            previous: None,
         }
     }
 
@@ -64,6 +80,35 @@ impl Cell {
         Self {
             content,
             cell_type: CellType::Collapsed,
+            previous: None,
+        }
+    }
+
+    pub fn marker(content: String, cell_type: CellType) -> Self {
+        Self {
+            content,
+            cell_type,
+            previous: None,
+        }
+    }
+
+    pub fn changed(previous: Cell, cell: Cell) -> Self {
+        Self {
+            previous: Some(Box::new(previous)),
+            ..cell
+        }
+    }
+
+    // Text as drawn, without colors
+    pub fn text(&self) -> String {
+        match &self.previous {
+            Some(previous) => [previous.content.as_str(), "→", &self.content]
+                .iter()
+                .filter(|part| !part.is_empty())
+                .copied()
+                .collect::<Vec<_>>()
+                .join(" "),
+            None => self.content.clone(),
         }
     }
 }
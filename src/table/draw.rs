@@ -2,6 +2,7 @@ use crate::args::Color;
 use std::iter;
 
 use colored::*;
+use regex::Regex;
 
 use super::{Cell, CellType, DrawOptions, Header};
 
@@ -18,17 +19,71 @@ const VERTICAL_RIGHT: char = '┤';
 const VERTICAL_LEFT: char = '├';
 const NEW_LINE: char = '\n';
 
-pub fn draw_table(headers: &[Header], rows: &Vec<Vec<Cell>>, options: DrawOptions) -> String {
+pub fn draw_table(headers: &[Header], rows: &[Vec<Cell>], options: DrawOptions) -> String {
+    if options.vertical {
+        return draw_records(headers, rows, &options);
+    }
+
     top_border(headers)
         .chain(header_row(headers, &options))
         .chain(rows.iter().flat_map(|row| {
-            return row_seperator(headers)
-                .chain(content_row(row, headers, &options));
+            row_seperator(headers)
+                .chain(content_row(row, headers, &options))
         }))
         .chain(bottom_border(headers))
         .collect::<String>()
 }
 
+// Draws each row as a block of fields, like psql's expanded display
+// Example:
+// -[ RECORD 1 ]-----------
+// name  │"Molecule Man"
+// age   │29
+fn draw_records(headers: &[Header], rows: &[Vec<Cell>], options: &DrawOptions) -> String {
+    let name_width = headers
+        .iter()
+        .map(|header| header.name.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let value_width = rows
+        .iter()
+        .flatten()
+        .map(|cell| cell.text().chars().count())
+        .max()
+        .unwrap_or(0);
+
+    rows.iter()
+        .enumerate()
+        .flat_map(|(i, row)| {
+            record_header(i + 1, name_width + 1 + value_width).chain(
+                headers.iter().zip(row.iter()).flat_map(move |(header, cell)| {
+                    format_header(&header.name, name_width, &options.color)
+                        .chars()
+                        .chain(iter::once(VERTICAL))
+                        .chain(format_cell(cell, 0, options).chars())
+                        .chain(iter::once(NEW_LINE))
+                        .collect::<Vec<_>>()
+                }),
+            )
+        })
+        .collect::<String>()
+}
+
+// Returns the header of a record, with its 1-based index
+// Example: -[ RECORD 3 ]-----------
+fn record_header(index: usize, width: usize) -> impl Iterator<Item = char> {
+    let label = format!("-[ RECORD {} ]-", index);
+    let dashes = width.saturating_sub(label.chars().count());
+
+    label
+        .chars()
+        .chain(iter::repeat_n('-', dashes))
+        .chain(iter::once(NEW_LINE))
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
 // Returns top border of table
 // Example: ┌────────┬────────┬────────┐
 fn top_border(headers: &[Header]) -> impl Iterator<Item = char> + '_ {
@@ -62,10 +117,10 @@ fn header_row<'a>(headers: &'a [Header], options: &'a DrawOptions) -> impl Itera
 
 // Returns a content row
 // Example: │"value1"│"value2"│"value3"│
-fn content_row<'a>(row: &'a Vec<Cell>, headers: &'a [Header], options: &'a DrawOptions) -> impl Iterator<Item = char> + 'a {
+fn content_row<'a>(row: &'a [Cell], headers: &'a [Header], options: &'a DrawOptions) -> impl Iterator<Item = char> + 'a {
     iter::once(VERTICAL)
         .chain(headers.iter().enumerate().flat_map(|(i, header)| {
-            format_cell(&row[i], header.max_width, &options.color)
+            format_cell(&row[i], header.max_width, options)
                 .chars()
                 .chain(iter::once(VERTICAL))
                 .collect::<Vec<_>>()
@@ -85,14 +140,40 @@ fn border(headers: &[Header], left: char, mid: char, right: char) -> impl Iterat
         .chain(iter::once(NEW_LINE))
 }
 
-fn format_cell(cell: &Cell, width: usize, color: &Color) -> String {
-    let padded = format!("{:<width$}", cell.content, width = width);
+fn format_cell(cell: &Cell, width: usize, options: &DrawOptions) -> String {
+    let text = cell.text();
 
-    match color {
-        Color::Never => padded,
-        Color::Auto => colorize(&padded, &cell.cell_type), // Fix with atty
-        Color::Always => colorize(&padded, &cell.cell_type),
-    }
+    let colored = match (&options.color, &options.highlight) {
+        (Color::Never, _) => return format!("{:<width$}", text, width = width),
+        (_, Some(pattern)) => highlight(&cell.content, &cell.cell_type, pattern),
+        (_, None) => colorize(&cell.content, &cell.cell_type), // Fix with atty for Auto
+    };
+
+    // The previous value of a changed cell, struck through
+    let colored = match &cell.previous {
+        Some(previous) => {
+            let mut parts = Vec::new();
+
+            if !previous.content.is_empty() {
+                let previous = colorize(&previous.content, &previous.cell_type);
+                parts.push(previous.strikethrough().to_string());
+            }
+
+            parts.push("→".bold().to_string());
+
+            if !colored.is_empty() {
+                parts.push(colored);
+            }
+
+            parts.join(" ")
+        }
+        None => colored,
+    };
+
+    // Pad outside of the color codes, as they don't take any width
+    let padding = width.saturating_sub(text.chars().count());
+
+    colored + &" ".repeat(padding)
 }
 
 fn format_header(s: &str, width: usize, color: &Color) -> String {
@@ -105,13 +186,98 @@ fn format_header(s: &str, width: usize, color: &Color) -> String {
     }
 }
 
+// Colorizes the cell, with the parts matching `pattern` highlighted.
+// Quotes around strings are never highlighted.
+fn highlight(content: &str, cell_type: &CellType, pattern: &Regex) -> String {
+    let (start, end) = match cell_type {
+        CellType::String if content.len() >= 2 => (1, content.len() - 1),
+        CellType::Collapsed => return colorize(content, cell_type),
+        _ => (0, content.len()),
+    };
+
+    let mut result = String::new();
+    let mut last = 0;
+
+    for m in pattern.find_iter(&content[start..end]) {
+        if m.is_empty() {
+            continue;
+        }
+
+        result += &colorize(&content[last..start + m.start()], cell_type);
+        result += &m.as_str().black().on_yellow().to_string();
+        last = start + m.end();
+    }
+
+    result + &colorize(&content[last..], cell_type)
+}
+
 fn colorize(content: &str, cell_type: &CellType) -> String {
+    if content.is_empty() {
+        return String::new();
+    }
+
     match cell_type {
         CellType::Null => content.white().dimmed(),
         CellType::Bool => content.white(),
         CellType::Number => content.yellow(),
         CellType::String => content.green(),
         CellType::Collapsed => content.white().dimmed(),
+        CellType::Added => content.green().bold(),
+        CellType::Removed => content.red().bold(),
+        CellType::Changed => content.yellow().bold(),
     }
     .to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(name: &str) -> Header {
+        Header {
+            name: String::from(name),
+            max_width: 0,
+        }
+    }
+
+    fn options() -> DrawOptions {
+        DrawOptions {
+            color: Color::Never,
+            flip: false,
+            vertical: true,
+            highlight: None,
+        }
+    }
+
+    #[test]
+    fn records() {
+        let headers = [header("name"), header("age")];
+        let rows = vec![
+            vec![Cell::string(String::from("\"Molecule Man\"")), Cell::number(String::from("29"))],
+            vec![Cell::string(String::from("\"Eternal Flame\"")), Cell::null(String::from("null"))],
+        ];
+
+        assert_eq!(
+            draw_table(&headers, &rows, options()),
+            "\
+-[ RECORD 1 ]-------
+name│\"Molecule Man\"
+age │29
+-[ RECORD 2 ]-------
+name│\"Eternal Flame\"
+age │null
+"
+        );
+    }
+
+    #[test]
+    fn no_rows() {
+        assert_eq!(draw_table(&[header("name")], &[], options()), "");
+    }
+
+    #[test]
+    fn record_header_is_never_cut() {
+        assert_eq!(record_header(12, 4).collect::<String>(), "-[ RECORD 12 ]-\n");
+        assert_eq!(record_header(1, 16).collect::<String>(), "-[ RECORD 1 ]---\n");
+    }
+}
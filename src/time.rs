@@ -0,0 +1,361 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Timestamps are handled as milliseconds since unix epoch, in UTC.
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+// Numbers below this are taken as epoch seconds, above as epoch millis.
+// 10^11 seconds is in the year 5138, while 10^11 millis is early 1973.
+const EPOCH_MILLIS_THRESHOLD: f64 = 1e11;
+
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+pub fn from_epoch(n: f64) -> i64 {
+    if n.abs() < EPOCH_MILLIS_THRESHOLD {
+        (n * 1000.0) as i64
+    } else {
+        n as i64
+    }
+}
+
+// Parses one of:
+//   RFC 3339 / ISO 8601: 2026-10-01T12:30:00Z, 2026-10-01 12:30:00.250+02:00, 2026-10-01
+//   Common log format:   10/Oct/2026:13:55:36 -0700, optionally within [..]
+pub fn parse(s: &str) -> Option<i64> {
+    let s = s.trim();
+    let s = s
+        .strip_prefix('[')
+        .and_then(|s| s.strip_suffix(']'))
+        .unwrap_or(s);
+
+    let mut cursor = Cursor { input: s.as_bytes(), pos: 0 };
+
+    let millis = if s.as_bytes().get(2) == Some(&b'/') {
+        parse_common_log(&mut cursor)?
+    } else {
+        parse_iso(&mut cursor)?
+    };
+
+    if cursor.pos != s.len() {
+        return None;
+    }
+
+    Some(millis)
+}
+
+// Parses a duration like `90s`, `5m`, `1h30m`, `2d` or `250ms` into millis.
+pub fn parse_duration(s: &str) -> Option<i64> {
+    if s.is_empty() {
+        return None;
+    }
+
+    let mut cursor = Cursor { input: s.as_bytes(), pos: 0 };
+    let mut total = 0;
+
+    while !cursor.done() {
+        let amount = cursor.number(1, 18)?;
+        let unit = cursor.take_while(|b| b.is_ascii_alphabetic());
+
+        let millis = match unit {
+            b"ms" => 1,
+            b"s" => 1_000,
+            b"m" => 60_000,
+            b"h" => 3_600_000,
+            b"d" => 86_400_000,
+            b"w" => 604_800_000,
+            _ => return None,
+        };
+
+        total = amount.checked_mul(millis)?.checked_add(total)?;
+    }
+
+    Some(total)
+}
+
+// Formats as RFC 3339 in UTC, like 2026-10-01T12:30:00Z, with millis only when
+// there are some.
+pub fn format(millis: i64) -> String {
+    let (year, month, day) = civil_from_days(millis.div_euclid(86_400_000));
+    let time = millis.rem_euclid(86_400_000);
+
+    let (hours, minutes, seconds) = (time / 3_600_000, time / 60_000 % 60, time / 1000 % 60);
+    let date = format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year, month, day, hours, minutes, seconds
+    );
+
+    match time % 1000 {
+        0 => format!("{}Z", date),
+        millis => format!("{}.{:03}Z", date, millis),
+    }
+}
+
+fn parse_iso(cursor: &mut Cursor) -> Option<i64> {
+    let year = cursor.number(4, 4)?;
+    let separator = cursor.one_of(b"-/")?;
+    let month = cursor.number(2, 2)?;
+    cursor.expect(separator)?;
+    let day = cursor.number(2, 2)?;
+
+    let date = days_from_civil(year, month, day)? * 86_400_000;
+
+    if cursor.done() {
+        return Some(date);
+    }
+
+    cursor.one_of(b"Tt ")?;
+    let time = parse_time(cursor)?;
+
+    // No offset means UTC
+    let offset = if cursor.done() {
+        0
+    } else {
+        parse_offset(cursor)?
+    };
+
+    Some(date + time - offset)
+}
+
+fn parse_common_log(cursor: &mut Cursor) -> Option<i64> {
+    let day = cursor.number(2, 2)?;
+    cursor.expect(b'/')?;
+    let name = cursor.take_while(|b| b.is_ascii_alphabetic());
+    let month = MONTHS
+        .iter()
+        .position(|m| m.as_bytes().eq_ignore_ascii_case(name))? as i64
+        + 1;
+    cursor.expect(b'/')?;
+    let year = cursor.number(4, 4)?;
+    cursor.expect(b':')?;
+
+    let date = days_from_civil(year, month, day)? * 86_400_000;
+    let time = parse_time(cursor)?;
+
+    cursor.expect(b' ')?;
+    let offset = parse_offset(cursor)?;
+
+    Some(date + time - offset)
+}
+
+// HH:MM[:SS[.fraction]]
+fn parse_time(cursor: &mut Cursor) -> Option<i64> {
+    let hour = cursor.number(2, 2)?;
+    cursor.expect(b':')?;
+    let minute = cursor.number(2, 2)?;
+
+    let mut second = 0;
+    let mut millis = 0;
+
+    if cursor.peek() == Some(b':') {
+        cursor.expect(b':')?;
+        second = cursor.number(2, 2)?;
+
+        if let Some(b'.' | b',') = cursor.peek() {
+            cursor.pos += 1;
+            let digits = cursor.take_while(|b| b.is_ascii_digit());
+
+            if digits.is_empty() {
+                return None;
+            }
+
+            // Only millisecond precision is kept
+            for i in 0..3 {
+                millis = millis * 10 + digits.get(i).map_or(0, |d| (d - b'0') as i64);
+            }
+        }
+    }
+
+    // Allow a leap second of 60
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    Some(((hour * 60 + minute) * 60 + second) * 1000 + millis)
+}
+
+// Z, +HH:MM, +HHMM or +HH. Returns the offset in millis.
+fn parse_offset(cursor: &mut Cursor) -> Option<i64> {
+    let sign = match cursor.one_of(b"Zz+-")? {
+        b'Z' | b'z' => return Some(0),
+        b'+' => 1,
+        _ => -1,
+    };
+
+    let hours = cursor.number(2, 2)?;
+
+    if cursor.peek() == Some(b':') {
+        cursor.pos += 1;
+    }
+
+    let minutes = if cursor.done() {
+        0
+    } else {
+        cursor.number(2, 2)?
+    };
+
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+
+    Some(sign * (hours * 60 + minutes) * 60_000)
+}
+
+// Days since 1970-01-01 for a proleptic gregorian date.
+// http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: i64, month: i64, day: i64) -> Option<i64> {
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    Some(era * 146_097 + doe - 719_468)
+}
+
+// The date of a number of days since 1970-01-01, the inverse of days_from_civil.
+// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+struct Cursor<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn done(&self) -> bool {
+        self.pos >= self.input.len()
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, expected: u8) -> Option<()> {
+        self.one_of(&[expected]).map(|_| ())
+    }
+
+    fn one_of(&mut self, options: &[u8]) -> Option<u8> {
+        let b = self.peek().filter(|b| options.contains(b))?;
+        self.pos += 1;
+        Some(b)
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(u8) -> bool) -> &'a [u8] {
+        let start = self.pos;
+
+        while self.peek().is_some_and(&predicate) {
+            self.pos += 1;
+        }
+
+        &self.input[start..self.pos]
+    }
+
+    // Reads between `min` and `max` ascii digits
+    fn number(&mut self, min: usize, max: usize) -> Option<i64> {
+        let start = self.pos;
+
+        while self.pos - start < max && self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+
+        if self.pos - start < min {
+            return None;
+        }
+
+        std::str::from_utf8(&self.input[start..self.pos])
+            .ok()?
+            .parse()
+            .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rfc3339() {
+        assert_eq!(parse("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse("2026-10-01T10:00:00Z"), Some(1_790_848_800_000));
+        assert_eq!(parse("2026-10-01T12:00:00+02:00"), Some(1_790_848_800_000));
+        assert_eq!(parse("2026-10-01 10:00:00.250"), Some(1_790_848_800_250));
+        assert_eq!(parse("2026-10-01T10:00"), Some(1_790_848_800_000));
+        assert_eq!(parse("2026-10-01"), Some(1_790_812_800_000));
+    }
+
+    #[test]
+    fn parses_common_log() {
+        assert_eq!(
+            parse("[01/Oct/2026:03:00:00 -0700]"),
+            Some(1_790_848_800_000)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_timestamps() {
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("2026-02-30"), None);
+        assert_eq!(parse("2026-10-01T25:00:00Z"), None);
+        assert_eq!(parse("2026-10-01T10:00:00Z trailing"), None);
+        assert_eq!(parse("1790848800"), None);
+    }
+
+    #[test]
+    fn epoch_seconds_and_millis() {
+        assert_eq!(from_epoch(1_790_848_800.0), 1_790_848_800_000);
+        assert_eq!(from_epoch(1_790_848_800_000.0), 1_790_848_800_000);
+    }
+
+    #[test]
+    fn formats_rfc3339() {
+        assert_eq!(format(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format(1_790_848_800_250), "2026-10-01T10:00:00.250Z");
+        assert_eq!(format(-1), "1969-12-31T23:59:59.999Z");
+        assert_eq!(parse(&format(1_790_848_805_007)), Some(1_790_848_805_007));
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("250ms"), Some(250));
+        assert_eq!(parse_duration("90s"), Some(90_000));
+        assert_eq!(parse_duration("1h30m"), Some(5_400_000));
+        assert_eq!(parse_duration("2d"), Some(172_800_000));
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("5"), None);
+        assert_eq!(parse_duration("5y"), None);
+        assert_eq!(parse_duration("999999999999999999w"), None);
+    }
+}